@@ -0,0 +1,314 @@
+//! API JSON versionnée (`/api/v1`) pour les applications tierces (appli mobile, bot Discord...).
+//!
+//! L'émission est choisie avec le paramètre `?show=slug` (par défaut, la première configurée).
+//! La lecture est publique et ne renvoie que les recommandations approuvées ; les chroniqueurs y
+//! sont désignés par leur identifiant, détaillé par `/chroniqueurs`. L'écriture demande
+//! un compte (voir [`ApiUser`]), et les propositions des invités partent en modération (réponse 202).
+//! Les erreurs sont renvoyées sous la forme `{"error": "message"}`.
+
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use std::net::SocketAddr;
+use axum::extract::{ConnectInfo, FromRequestParts, Path, Query, State};
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::auth::{self, AuthError, LoginError, User};
+use crate::categories::{Category, CategoryRegistry};
+use crate::feed::Episode;
+use crate::hosts::{Host, HostRegistry};
+use crate::search::{Hit, SearchQuery};
+use crate::store::{Recommendation, RecommendationInput, Status, StoreError};
+use crate::{AppState, ShowState};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/episodes", get(list_episodes))
+        .route("/chroniqueurs", get(list_hosts))
+        .route("/categories", get(list_categories))
+        .route("/search", get(search))
+        .route(
+            "/episodes/{episode_id}/recommendations",
+            get(list_recommendations).post(create_recommendation),
+        )
+        .route(
+            "/episodes/{episode_id}/recommendations/{id}",
+            put(update_recommendation).delete(delete_recommendation),
+        )
+}
+
+/// Erreur renvoyée en JSON avec le statut HTTP correspondant.
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError { status, message: message.into() }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ErrorBody { error: self.message })).into_response()
+    }
+}
+
+// Les rejets des extracteurs d'axum sont renvoyés en JSON plutôt qu'en texte brut
+impl From<JsonRejection> for ApiError {
+    fn from(e: JsonRejection) -> Self {
+        ApiError::new(e.status(), e.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(e: PathRejection) -> Self {
+        ApiError::new(e.status(), e.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(e: QueryRejection) -> Self {
+        ApiError::new(e.status(), e.body_text())
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(e: AuthError) -> Self {
+        let status = match e {
+            AuthError::NotLoggedIn => StatusCode::UNAUTHORIZED,
+            AuthError::BadCsrf | AuthError::Forbidden => StatusCode::FORBIDDEN,
+        };
+        ApiError::new(status, e.to_string())
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(e: StoreError) -> Self {
+        let status = match e {
+            StoreError::NotFound => StatusCode::NOT_FOUND,
+            StoreError::Conflict => StatusCode::CONFLICT,
+            StoreError::Io(_) | StoreError::Json(_) | StoreError::Corrupt(..) | StoreError::Sqlite(_) => {
+                eprintln!("Erreur de stockage : {}", e);
+                return ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Erreur de stockage");
+            }
+        };
+        ApiError::new(status, e.to_string())
+    }
+}
+
+/// Utilisateur authentifié pour les routes d'écriture : en-tête `Authorization: Basic`,
+/// ou cookie de session du site accompagné de son jeton CSRF dans l'en-tête `X-CSRF-Token`.
+pub struct ApiUser(User);
+
+impl FromRequestParts<AppState> for ApiUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some((login, password)) = auth::basic_credentials(&parts.headers) {
+            // Sans adresse de connexion (hors `axum::serve`), toutes les tentatives partagent la même limite
+            let peer = parts.extensions.get::<ConnectInfo<SocketAddr>>()
+                .map_or(SocketAddr::from(([0, 0, 0, 0], 0)), |info| info.0);
+            return state.auth.verify_limited(&state.logins, &parts.headers, peer, &login, &password).await
+                .map(ApiUser)
+                .map_err(|e| match e {
+                    LoginError::Invalid => ApiError::new(StatusCode::UNAUTHORIZED, e.to_string()),
+                    LoginError::TooManyAttempts(_) => ApiError::new(StatusCode::TOO_MANY_REQUESTS, e.to_string()),
+                });
+        }
+        let user = state.auth.current_user(&parts.headers)
+            .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "Authentification requise"))?;
+        let csrf = parts.headers.get("x-csrf-token").and_then(|v| v.to_str().ok()).unwrap_or("");
+        user.check_csrf(csrf)
+            .map_err(|_| ApiError::new(StatusCode::FORBIDDEN, "Jeton CSRF manquant ou invalide"))?;
+        Ok(ApiUser(user.user))
+    }
+}
+
+#[derive(Deserialize)]
+struct ShowQuery {
+    show: Option<String>,
+}
+
+fn find_show(state: &AppState, query: Result<Query<ShowQuery>, QueryRejection>) -> Result<&ShowState, ApiError> {
+    let Query(query) = query?;
+    state.show(query.show.as_deref())
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Émission inconnue"))
+}
+
+/// Vérifie que l'épisode figure dans le flux en cache.
+fn find_episode(show: &ShowState, episode_id: &str) -> Result<(), ApiError> {
+    let episodes = show.feed.episodes();
+    if episodes.is_empty() {
+        return Err(ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "Flux du podcast indisponible"));
+    }
+    if !episodes.iter().any(|ep| ep.guid == episode_id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "Épisode inconnu"));
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct EpisodeWithRecommendations<'a> {
+    #[serde(flatten)]
+    episode: &'a Episode,
+    recommendations: &'a [Recommendation],
+}
+
+async fn list_episodes(
+    State(state): State<AppState>,
+    query: Result<Query<ShowQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let show = find_show(&state, query)?;
+    let episodes = show.feed.episodes();
+    if episodes.is_empty() {
+        return Err(ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "Flux du podcast indisponible"));
+    }
+    let mut map = show.store.all()?;
+    for list in map.values_mut() {
+        list.retain(|rec| rec.status == Status::Approved);
+    }
+    let body: Vec<EpisodeWithRecommendations> = episodes.iter()
+        .map(|episode| EpisodeWithRecommendations {
+            episode,
+            recommendations: map.get(&episode.guid).map(Vec::as_slice).unwrap_or_default(),
+        })
+        .collect();
+    Ok(Json(body).into_response())
+}
+
+async fn list_recommendations(
+    State(state): State<AppState>,
+    query: Result<Query<ShowQuery>, QueryRejection>,
+    path: Result<Path<String>, PathRejection>,
+) -> Result<Json<Vec<Recommendation>>, ApiError> {
+    let show = find_show(&state, query)?;
+    let Path(episode_id) = path?;
+    // Les recommandations restent lisibles même si l'épisode a disparu du flux
+    let mut list = show.store.list(&episode_id)?;
+    list.retain(|rec| rec.status == Status::Approved);
+    if list.is_empty() {
+        find_episode(show, &episode_id)?;
+    }
+    Ok(Json(list))
+}
+
+/// Registre des chroniqueurs, pour afficher les noms des recommandations.
+async fn list_hosts(State(state): State<AppState>) -> Json<Vec<Host>> {
+    Json(state.hosts.all())
+}
+
+/// Catégories possibles pour `type_media`, dans l'ordre d'affichage.
+async fn list_categories(State(state): State<AppState>) -> Json<Vec<Category>> {
+    Json(state.categories.all())
+}
+
+#[derive(Serialize)]
+struct SearchResults {
+    query: String,
+    results: Vec<Hit>,
+}
+
+/// Recherche plein texte, toutes émissions confondues (voir [`crate::search`]).
+async fn search(
+    State(state): State<AppState>,
+    query: Result<Query<SearchQuery>, QueryRejection>,
+) -> Result<Json<SearchResults>, ApiError> {
+    let Query(SearchQuery { q }) = query?;
+    let results = state.search.search(&state, &q)?;
+    Ok(Json(SearchResults { query: q, results }))
+}
+
+/// Corps des requêtes POST et PUT.
+#[derive(Deserialize)]
+struct RecommendationBody {
+    #[serde(flatten)]
+    input: RecommendationInput,
+    /// Pour PUT : date de modification connue du client, pour détecter les modifications concurrentes.
+    updated_at: Option<DateTime<Utc>>,
+}
+
+fn validate(
+    hosts: &HostRegistry,
+    categories: &CategoryRegistry,
+    show: &ShowState,
+    episode_id: &str,
+    body: RecommendationBody,
+) -> Result<(RecommendationInput, Option<DateTime<Utc>>), ApiError> {
+    let input = body.input.validated()
+        .map_err(|message| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, message))?;
+    hosts.check_known(&input.chroniqueurs)
+        .and_then(|()| categories.check_known(&input.type_media))
+        .and_then(|()| show.check_timestamp(episode_id, input.timestamp))
+        .map_err(|message| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, message))?;
+    Ok((input, body.updated_at))
+}
+
+async fn create_recommendation(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    query: Result<Query<ShowQuery>, QueryRejection>,
+    path: Result<Path<String>, PathRejection>,
+    body: Result<Json<RecommendationBody>, JsonRejection>,
+) -> Result<(StatusCode, Json<Recommendation>), ApiError> {
+    let show = find_show(&state, query)?;
+    let Path(episode_id) = path?;
+    let Json(body) = body?;
+    find_episode(show, &episode_id)?;
+    let (input, _) = validate(&state.hosts, &state.categories, show, &episode_id, body)?;
+    let (status, code) = if user.can_publish() {
+        (Status::Approved, StatusCode::CREATED)
+    } else {
+        (Status::Pending, StatusCode::ACCEPTED)
+    };
+    let rec = input.into_recommendation(status);
+    show.store.insert(&episode_id, &rec)?;
+    Ok((code, Json(rec)))
+}
+
+/// Vérifie que la recommandation `id` appartient à l'épisode de l'URL et que `user` peut la modifier.
+fn find_modifiable(show: &ShowState, user: &User, episode_id: &str, id: Uuid) -> Result<(), ApiError> {
+    let list = show.store.list(episode_id)?;
+    let rec = list.iter().find(|rec| rec.id == id).ok_or(StoreError::NotFound)?;
+    user.check_can_modify(rec)?;
+    Ok(())
+}
+
+async fn update_recommendation(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    query: Result<Query<ShowQuery>, QueryRejection>,
+    path: Result<Path<(String, Uuid)>, PathRejection>,
+    body: Result<Json<RecommendationBody>, JsonRejection>,
+) -> Result<Json<Recommendation>, ApiError> {
+    let show = find_show(&state, query)?;
+    let Path((episode_id, id)) = path?;
+    let Json(body) = body?;
+    find_modifiable(show, &user, &episode_id, id)?;
+    let (input, seen_updated_at) = validate(&state.hosts, &state.categories, show, &episode_id, body)?;
+    let rec = show.store.update(id, seen_updated_at, input)?;
+    Ok(Json(rec))
+}
+
+async fn delete_recommendation(
+    State(state): State<AppState>,
+    ApiUser(user): ApiUser,
+    query: Result<Query<ShowQuery>, QueryRejection>,
+    path: Result<Path<(String, Uuid)>, PathRejection>,
+) -> Result<StatusCode, ApiError> {
+    let show = find_show(&state, query)?;
+    let Path((episode_id, id)) = path?;
+    find_modifiable(show, &user, &episode_id, id)?;
+    show.store.delete(id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
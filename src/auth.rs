@@ -0,0 +1,421 @@
+//! Comptes des chroniqueurs, sessions et protection CSRF.
+//!
+//! Les comptes sont déclarés dans la configuration (`[[users]]`) avec un hash argon2 du mot
+//! de passe. Une connexion ouvre une session en mémoire, désignée par le cookie `pap_session` ;
+//! chaque session a son jeton CSRF, à renvoyer avec les formulaires d'écriture.
+//! Le rôle du compte décide ensuite de ce que l'utilisateur peut faire (voir [`User`]).
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use argon2::Argon2;
+use argon2::password_hash::phc::PasswordHash;
+use argon2::password_hash::{PasswordHasher, PasswordVerifier};
+use axum::extract::{ConnectInfo, Form, FromRequestParts, OptionalFromRequestParts, Query, State};
+use axum::http::header::{COOKIE, LOCATION, RETRY_AFTER, SET_COOKIE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::Deserialize;
+use crate::AppState;
+use crate::store::Recommendation;
+use crate::throttle::Throttle;
+
+const SESSION_COOKIE: &str = "pap_session";
+const SESSION_TTL: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// Tentatives de connexion manquées acceptées par adresse pendant `FAILED_LOGIN_WINDOW_SECS`.
+pub const FAILED_LOGIN_LIMIT: usize = 10;
+pub const FAILED_LOGIN_WINDOW_SECS: u64 = 900;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/login", get(login_page).post(login))
+        .route("/logout", post(logout))
+}
+
+/// Compte d'un chroniqueur, déclaré dans la configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Account {
+    /// Identifiant saisi à la connexion.
+    pub login: String,
+    /// Nom affiché, tel qu'il apparaît dans les recommandations.
+    pub name: String,
+    /// Hash argon2 au format PHC (`pap hash-password`).
+    pub password_hash: String,
+    #[serde(default)]
+    pub role: Role,
+    /// Fiche du registre des chroniqueurs (par défaut, celle qui porte le nom du compte).
+    #[serde(default)]
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Modifie et supprime toutes les recommandations.
+    Admin,
+    /// Publie des recommandations et modifie celles où il figure.
+    #[default]
+    Chroniqueur,
+    /// Propose des recommandations, publiées après modération.
+    Guest,
+}
+
+/// Utilisateur authentifié.
+#[derive(Debug, Clone)]
+pub struct User {
+    /// Identifiant de connexion.
+    pub login: String,
+    pub name: String,
+    pub role: Role,
+    /// Identifiant dans le registre des chroniqueurs.
+    pub host: Option<String>,
+}
+
+impl User {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    /// Ses recommandations sont publiées directement ; sinon elles vont en modération.
+    pub fn can_publish(&self) -> bool {
+        self.role != Role::Guest
+    }
+
+    /// Peut modifier ou supprimer `rec`.
+    pub fn can_modify(&self, rec: &Recommendation) -> bool {
+        match self.role {
+            Role::Admin => true,
+            Role::Chroniqueur => self.host.as_ref().is_some_and(|host| rec.chroniqueurs.contains(host)),
+            Role::Guest => false,
+        }
+    }
+
+    /// Refuse la modification de `rec` si l'utilisateur n'y a pas droit.
+    pub fn check_can_modify(&self, rec: &Recommendation) -> Result<(), AuthError> {
+        if self.can_modify(rec) { Ok(()) } else { Err(AuthError::Forbidden) }
+    }
+}
+
+struct Session {
+    user: User,
+    csrf_token: String,
+    expires: Instant,
+}
+
+/// Comptes et sessions ouvertes (perdues au redémarrage du serveur).
+pub struct Auth {
+    accounts: Vec<Account>,
+    sessions: Mutex<HashMap<String, Session>>,
+    /// Cookie de session marqué `Secure` (site servi en HTTPS).
+    secure_cookies: bool,
+}
+
+impl Auth {
+    pub fn new(accounts: Vec<Account>, secure_cookies: bool) -> Auth {
+        Auth { accounts, sessions: Mutex::new(HashMap::new()), secure_cookies }
+    }
+
+    pub fn has_accounts(&self) -> bool {
+        !self.accounts.is_empty()
+    }
+
+    /// Vérifie l'identifiant et le mot de passe.
+    pub async fn verify(&self, login: &str, password: &str) -> Option<User> {
+        let account = self.accounts.iter().find(|a| a.login == login)?;
+        let hash = account.password_hash.clone();
+        let password = password.to_string();
+        // argon2 est volontairement coûteux : on ne bloque pas les autres requêtes
+        let valid = tokio::task::spawn_blocking(move || {
+            PasswordHash::new(&hash)
+                .is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+        })
+        .await
+        .unwrap_or(false);
+        valid.then(|| User { login: account.login.clone(), name: account.name.clone(), role: account.role, host: account.host.clone() })
+    }
+
+    /// Comme `verify`, en limitant par adresse les tentatives manquées (`throttle`).
+    pub async fn verify_limited(
+        &self,
+        throttle: &Throttle,
+        headers: &HeaderMap,
+        peer: SocketAddr,
+        login: &str,
+        password: &str,
+    ) -> Result<User, LoginError> {
+        throttle.check(headers, peer).map_err(LoginError::TooManyAttempts)?;
+        let user = self.verify(login, password).await.ok_or(LoginError::Invalid)?;
+        throttle.release(headers, peer);
+        Ok(user)
+    }
+
+    /// En-tête `Set-Cookie` du cookie de session.
+    fn session_cookie(&self, value: &str, max_age: Duration) -> String {
+        format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}",
+            SESSION_COOKIE, value, max_age.as_secs(), if self.secure_cookies { "; Secure" } else { "" }
+        )
+    }
+
+    /// Ouvre une session et renvoie son identifiant, à placer dans le cookie.
+    fn open_session(&self, user: User) -> String {
+        let token = random_token();
+        let mut sessions = self.sessions.lock().unwrap();
+        let now = Instant::now();
+        sessions.retain(|_, s| s.expires > now);
+        sessions.insert(token.clone(), Session { user, csrf_token: random_token(), expires: now + SESSION_TTL });
+        token
+    }
+
+    fn close_session(&self, token: &str) {
+        self.sessions.lock().unwrap().remove(token);
+    }
+
+    /// Utilisateur de la session désignée par le cookie de la requête.
+    pub fn current_user(&self, headers: &HeaderMap) -> Option<CurrentUser> {
+        let token = session_token(headers)?;
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(token).filter(|s| s.expires > Instant::now())?;
+        Some(CurrentUser {
+            user: session.user.clone(),
+            session: token.to_string(),
+            csrf_token: session.csrf_token.clone(),
+        })
+    }
+}
+
+/// Hash argon2 d'un mot de passe, à recopier dans `[[users]]`.
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    Ok(Argon2::default().hash_password(password.as_bytes())?.to_string())
+}
+
+pub fn is_valid_hash(hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok()
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).expect("générateur aléatoire du système indisponible");
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn session_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get_all(COOKIE).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('='))
+}
+
+/// Comparaison en temps constant, pour ne pas révéler le jeton par la durée de la réponse.
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Chroniqueur connecté par cookie de session ; extracteur des routes d'écriture.
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub user: User,
+    session: String,
+    pub csrf_token: String,
+}
+
+impl CurrentUser {
+    /// Vérifie le jeton CSRF renvoyé par le formulaire.
+    pub fn check_csrf(&self, token: &str) -> Result<(), AuthError> {
+        if same_token(token, &self.csrf_token) { Ok(()) } else { Err(AuthError::BadCsrf) }
+    }
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    NotLoggedIn,
+    BadCsrf,
+    /// Connecté, mais sans le droit de faire cette action.
+    Forbidden,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::NotLoggedIn => write!(f, "Connectez-vous pour modifier les recommandations"),
+            AuthError::BadCsrf => write!(f, "Session expirée ou formulaire invalide : rechargez la page"),
+            AuthError::Forbidden => write!(f, "Vous n'avez pas le droit de modifier cette recommandation"),
+        }
+    }
+}
+
+/// Tentative de connexion refusée.
+#[derive(Debug)]
+pub enum LoginError {
+    Invalid,
+    /// Trop de tentatives manquées depuis cette adresse : secondes à attendre.
+    TooManyAttempts(u64),
+}
+
+impl fmt::Display for LoginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginError::Invalid => write!(f, "Identifiant ou mot de passe incorrect"),
+            LoginError::TooManyAttempts(_) => write!(f, "Trop de tentatives de connexion : réessayez dans quelques minutes"),
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AuthError::NotLoggedIn => StatusCode::UNAUTHORIZED,
+            AuthError::BadCsrf | AuthError::Forbidden => StatusCode::FORBIDDEN,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        state.auth.current_user(&parts.headers).ok_or(AuthError::NotLoggedIn)
+    }
+}
+
+/// Pour les pages publiques, qui affichent les actions d'écriture seulement aux chroniqueurs connectés.
+impl OptionalFromRequestParts<AppState> for CurrentUser {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Option<Self>, Self::Rejection> {
+        Ok(state.auth.current_user(&parts.headers))
+    }
+}
+
+/// Administrateur connecté ; extracteur des pages d'administration.
+///
+/// Sans session, renvoie vers la page de connexion ; les autres comptes reçoivent un 403.
+pub struct Admin(pub CurrentUser);
+
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        match state.auth.current_user(&parts.headers) {
+            Some(user) if user.user.is_admin() => Ok(Admin(user)),
+            Some(_) => Err((StatusCode::FORBIDDEN, "Page réservée aux administrateurs").into_response()),
+            None => Err((StatusCode::SEE_OTHER, [(LOCATION, format!("/login?next={}", parts.uri.path()))]).into_response()),
+        }
+    }
+}
+
+/// N'accepte que les redirections internes au site après connexion.
+fn safe_next(next: Option<&str>) -> &str {
+    match next {
+        Some(next) if next.starts_with('/') && !next.starts_with("//") && !next.contains('\\') => next,
+        _ => "/",
+    }
+}
+
+fn redirect_with_cookie(location: &str, cookie: String) -> Response {
+    (StatusCode::SEE_OTHER, [(LOCATION, location.to_string()), (SET_COOKIE, cookie)]).into_response()
+}
+
+#[derive(Deserialize)]
+struct LoginQuery {
+    next: Option<String>,
+}
+
+async fn login_page(Query(query): Query<LoginQuery>) -> Response {
+    login_form(safe_next(query.next.as_deref()), None)
+}
+
+fn login_form(next: &str, error: Option<&str>) -> Response {
+    let error_html = error.map(|e| format!(
+        "<p style='color:#c00;'>{}</p>", htmlescape::encode_minimal(e)
+    )).unwrap_or_default();
+    let html = format!(
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Connexion</title>
+        </head>
+        <body style="font-family:sans-serif;display:flex;align-items:center;justify-content:center;min-height:100vh;margin:0;background:#f3f6fa;">
+            <form method="post" action="/login" style="background:#fff;border-radius:18px;box-shadow:0 2px 16px #0002;padding:2em 2.5em;min-width:280px;display:flex;flex-direction:column;gap:0.6em;">
+                <h1 style="margin-top:0;">Connexion</h1>
+                {error}
+                <input type="hidden" name="next" value="{next}">
+                <label for="login">Identifiant</label>
+                <input id="login" name="login" required autocomplete="username" style="padding:0.6em;border-radius:8px;border:1px solid #ccc;">
+                <label for="password">Mot de passe</label>
+                <input id="password" name="password" type="password" required autocomplete="current-password" style="padding:0.6em;border-radius:8px;border:1px solid #ccc;">
+                <button type="submit" style="margin-top:1em;background:linear-gradient(90deg,#ffb347,#ffcc33);font-weight:bold;border:none;border-radius:2em;padding:0.7em 2.2em;cursor:pointer;">Se connecter</button>
+                <a href="{next}" style="text-align:center;color:#4a90e2;">Retour</a>
+            </form>
+        </body>
+        </html>"#,
+        error = error_html,
+        next = htmlescape::encode_attribute(next),
+    );
+    let status = if error.is_some() { StatusCode::UNAUTHORIZED } else { StatusCode::OK };
+    (status, Html(html)).into_response()
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    login: String,
+    password: String,
+    next: Option<String>,
+}
+
+async fn login(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<LoginForm>,
+) -> Response {
+    let next = safe_next(form.next.as_deref());
+    let user = match state.auth.verify_limited(&state.logins, &headers, peer, form.login.trim(), &form.password).await {
+        Ok(user) => user,
+        Err(e @ LoginError::Invalid) => return login_form(next, Some(&e.to_string())),
+        Err(e @ LoginError::TooManyAttempts(wait)) => {
+            let mut response = login_form(next, Some(&e.to_string()));
+            *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+            response.headers_mut().insert(RETRY_AFTER, wait.into());
+            return response;
+        }
+    };
+    let token = state.auth.open_session(user);
+    redirect_with_cookie(next, state.auth.session_cookie(&token, SESSION_TTL))
+}
+
+#[derive(Deserialize)]
+struct LogoutForm {
+    csrf_token: String,
+    next: Option<String>,
+}
+
+async fn logout(State(state): State<AppState>, user: CurrentUser, Form(form): Form<LogoutForm>) -> Response {
+    if let Err(e) = user.check_csrf(&form.csrf_token) {
+        return e.into_response();
+    }
+    state.auth.close_session(&user.session);
+    redirect_with_cookie(safe_next(form.next.as_deref()), state.auth.session_cookie("", Duration::ZERO))
+}
+
+/// Identifiants d'un en-tête `Authorization: Basic` (clients de l'API).
+pub fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(axum::http::header::AUTHORIZATION)?.to_str().ok()?;
+    let encoded = value.strip_prefix("Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    let (login, password) = String::from_utf8(decoded).ok()?.split_once(':').map(|(l, p)| (l.to_string(), p.to_string()))?;
+    Some((login, password))
+}
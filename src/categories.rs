@@ -0,0 +1,354 @@
+//! Catégories des recommandations (film, livre...), communes à toutes les émissions.
+//!
+//! Les recommandations désignent leur catégorie par identifiant (`type_media`) ; le libellé,
+//! la couleur, l'icône et l'ordre d'affichage sont gardés dans un fichier JSON
+//! (`categories_file`) et modifiables par les administrateurs sur `/admin/categories`.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use axum::extract::{Form, Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::http::header::LOCATION;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use crate::auth::Admin;
+use crate::hosts::{admin_page, fold_accents};
+use crate::show::is_valid_slug;
+use crate::store::{self, StoreError};
+use crate::{AppState, store_error};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Category {
+    /// Identifiant stable, enregistré dans `type_media`.
+    pub id: String,
+    pub label: String,
+    /// Couleur de la pastille (`#rrggbb`).
+    pub color: String,
+    /// Emoji affiché devant le libellé.
+    #[serde(default)]
+    pub icon: String,
+    /// Ordre dans le formulaire et les filtres (croissant).
+    #[serde(default)]
+    pub position: i32,
+}
+
+/// Catégories de départ, tant que le fichier n'existe pas.
+fn default_categories() -> Vec<Category> {
+    [
+        ("film", "Film", "#ffb347", "🎬"),
+        ("livre", "Livre", "#a3d977", "📚"),
+        ("chaine", "Chaîne", "#ff5e5e", "📺"),
+        ("compte", "Compte", "#e1306c", "📱"),
+        ("musique", "Musique", "#1db954", "🎵"),
+        ("serie", "Série", "#4a90e2", "🍿"),
+        ("jeu", "Jeu", "#f6c3ff", "🎮"),
+        ("autre", "Autre", "#b88a2a", "✨"),
+    ]
+        .into_iter()
+        .zip(0..)
+        .map(|((id, label, color, icon), position)| Category {
+            id: id.to_string(),
+            label: label.to_string(),
+            color: color.to_string(),
+            icon: icon.to_string(),
+            position,
+        })
+        .collect()
+}
+
+/// Catégorie d'un texte libre enregistré par une version précédente (« chaine youtube »,
+/// « Série »...) ; ce qui n'est pas reconnu devient `autre`. Seule règle de reprise, pour les
+/// deux stockages (lecture des fichiers JSON, migration 4 de SQLite).
+///
+/// `None` pour une valeur déjà en forme d'identifiant : ce peut être une catégorie créée par un
+/// administrateur, à garder telle quelle.
+pub fn legacy_id(text: &str) -> Option<&'static str> {
+    if is_valid_slug(text) {
+        return None;
+    }
+    let text = fold_accents(&text.trim().to_lowercase());
+    let id = ["film", "livre", "chaine", "compte", "musique", "serie", "jeu"]
+        .into_iter()
+        .find(|prefix| text.starts_with(prefix))
+        .unwrap_or("autre");
+    Some(id)
+}
+
+pub struct CategoryRegistry {
+    path: PathBuf,
+    categories: Mutex<Vec<Category>>,
+}
+
+impl CategoryRegistry {
+    /// Ouvre le fichier des catégories ; sans fichier, les catégories de départ.
+    pub fn open(path: &Path) -> Result<CategoryRegistry, StoreError> {
+        let categories = match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| StoreError::Corrupt(path.to_path_buf(), e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => default_categories(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(CategoryRegistry { path: path.to_path_buf(), categories: Mutex::new(categories) })
+    }
+
+    /// Toutes les catégories, dans l'ordre d'affichage.
+    pub fn all(&self) -> Vec<Category> {
+        let mut categories = self.categories.lock().unwrap().clone();
+        categories.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.label.cmp(&b.label)));
+        categories
+    }
+
+    pub fn get(&self, id: &str) -> Option<Category> {
+        self.categories.lock().unwrap().iter().find(|c| c.id == id).cloned()
+    }
+
+    /// Catégorie à afficher pour `id` ; une catégorie supprimée du fichier garde son identifiant pour libellé.
+    pub fn display(&self, id: &str) -> Category {
+        self.get(id).unwrap_or_else(|| Category {
+            id: id.to_string(),
+            label: id.to_string(),
+            color: "#888888".to_string(),
+            icon: String::new(),
+            position: i32::MAX,
+        })
+    }
+
+    /// Refuse une saisie dont la catégorie n'existe pas.
+    pub fn check_known(&self, id: &str) -> Result<(), String> {
+        match self.get(id) {
+            Some(_) => Ok(()),
+            None => Err(format!("Catégorie inconnue : {}", id)),
+        }
+    }
+
+    /// Ajoute ou remplace (même identifiant) une catégorie.
+    pub fn save(&self, category: Category) -> Result<(), StoreError> {
+        self.write(|categories| match categories.iter_mut().find(|c| c.id == category.id) {
+            Some(existing) => *existing = category,
+            None => categories.push(category),
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), StoreError> {
+        self.write(|categories| categories.retain(|c| c.id != id))
+    }
+
+    /// Applique `change` à une copie, l'enregistre, puis la garde en mémoire.
+    fn write(&self, change: impl FnOnce(&mut Vec<Category>)) -> Result<(), StoreError> {
+        let mut categories = self.categories.lock().unwrap();
+        let mut updated = categories.clone();
+        change(&mut updated);
+        store::write_json_file(&self.path, &updated)?;
+        *categories = updated;
+        Ok(())
+    }
+}
+
+// Pages d'administration
+
+const ADMIN_PAGE: &str = "/admin/categories";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(ADMIN_PAGE, get(categories_page).post(save_category))
+        .route("/admin/categories/{id}", get(edit_category_page))
+        .route("/admin/categories/{id}/delete", post(delete_category))
+}
+
+/// Pastille de la catégorie, telle qu'affichée sur les cartes.
+pub fn badge(category: &Category) -> String {
+    format!(
+        "<span class='reco-type' style='background:{};color:#fff;font-size:1em;font-style:normal;margin-left:0.1em;border-radius:50px;padding:0.18em 1.2em;box-shadow:0 2px 8px #0001;display:inline-block;letter-spacing:0.01em;min-width:70px;text-align:center;font-weight:600;vertical-align:middle;'>{} {}</span>",
+        htmlescape::encode_attribute(&category.color),
+        htmlescape::encode_minimal(&category.icon),
+        htmlescape::encode_minimal(&category.label)
+    )
+}
+
+/// Formulaire de création (`category` vide) ou de modification d'une catégorie.
+fn category_form(category: Option<&Category>, next_position: i32, csrf_token: &str) -> String {
+    let id_input = match category {
+        Some(category) => format!(
+            "<input type='hidden' name='id' value='{id}'><input type='hidden' name='existing' value='true'>\
+             <p>Identifiant : <code>{id}</code></p>",
+            id = htmlescape::encode_attribute(&category.id)
+        ),
+        None => "<label for='id'>Identifiant (minuscules, chiffres, tirets ; enregistré dans les recommandations)</label>\
+                 <input id='id' name='id' required pattern='[a-z0-9-]+'>".to_string(),
+    };
+    format!(
+        "<form class='admin-form' method='post' action='{action}' style='background:#fff;border-radius:14px;box-shadow:0 2px 8px #0001;padding:1.2em 1.5em;'>\
+            <input type='hidden' name='csrf_token' value='{csrf}'>\
+            {id_input}\
+            <label for='label'>Libellé</label>\
+            <input id='label' name='label' required value='{label}'>\
+            <label for='color'>Couleur</label>\
+            <input id='color' name='color' type='color' value='{color}'>\
+            <label for='icon'>Icône (emoji)</label>\
+            <input id='icon' name='icon' value='{icon}'>\
+            <label for='position'>Ordre d'affichage</label>\
+            <input id='position' name='position' type='number' value='{position}'>\
+            <button type='submit'>Enregistrer</button>\
+        </form>",
+        action = ADMIN_PAGE,
+        csrf = csrf_token,
+        id_input = id_input,
+        label = htmlescape::encode_attribute(category.map_or("", |c| c.label.as_str())),
+        color = htmlescape::encode_attribute(category.map_or("#b88a2a", |c| c.color.as_str())),
+        icon = htmlescape::encode_attribute(category.map_or("", |c| c.icon.as_str())),
+        position = category.map_or(next_position, |c| c.position),
+    )
+}
+
+async fn categories_page(State(state): State<AppState>, Admin(user): Admin) -> Response {
+    let categories = state.categories.all();
+    let rows: String = categories.iter().map(|category| format!(
+        "<li style='margin-bottom:0.6em;list-style:none;'>{badge} <a href='{page}/{id}'>Modifier</a> <code style='color:#888;'>{id}</code></li>",
+        badge = badge(category),
+        page = ADMIN_PAGE,
+        id = htmlescape::encode_attribute(&category.id),
+    )).collect();
+    let next_position = categories.iter().map(|c| c.position).max().map_or(0, |p| p + 1);
+    admin_page("Catégories", format!(
+        "<ul style='padding:0;'>{}</ul><h2>Nouvelle catégorie</h2>{}",
+        rows,
+        category_form(None, next_position, &user.csrf_token)
+    ))
+}
+
+async fn edit_category_page(State(state): State<AppState>, Admin(user): Admin, UrlPath(id): UrlPath<String>) -> Response {
+    let Some(category) = state.categories.get(&id) else {
+        return (StatusCode::NOT_FOUND, "Catégorie inconnue").into_response();
+    };
+    let delete = format!(
+        "<form method='post' action='{page}/{id}/delete' style='margin-top:1.5em;'>\
+            <input type='hidden' name='csrf_token' value='{csrf}'>\
+            <button type='submit' style='background:#c00;color:#fff;border:none;border-radius:2em;padding:0.5em 1.6em;cursor:pointer;'>Supprimer la catégorie</button>\
+        </form>",
+        page = ADMIN_PAGE,
+        id = htmlescape::encode_attribute(&category.id),
+        csrf = user.csrf_token,
+    );
+    admin_page(&category.label, format!("{}{}", category_form(Some(&category), 0, &user.csrf_token), delete))
+}
+
+#[derive(Deserialize)]
+struct CategoryForm {
+    csrf_token: String,
+    id: String,
+    /// Présent quand le formulaire modifie une catégorie existante.
+    #[serde(default)]
+    existing: bool,
+    label: String,
+    color: String,
+    icon: String,
+    position: i32,
+}
+
+/// Couleur au format `#rrggbb`, seul format produit par `<input type='color'>`.
+fn is_valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+async fn save_category(State(state): State<AppState>, Admin(user): Admin, Form(form): Form<CategoryForm>) -> Response {
+    if let Err(e) = user.check_csrf(&form.csrf_token) {
+        return e.into_response();
+    }
+    let id = form.id.trim().to_string();
+    if !is_valid_slug(&id) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Identifiant invalide (minuscules, chiffres et tirets uniquement)").into_response();
+    }
+    if state.categories.get(&id).is_some() != form.existing {
+        let message = if form.existing { "Catégorie inconnue" } else { "Cet identifiant est déjà utilisé" };
+        return (StatusCode::CONFLICT, message).into_response();
+    }
+    let label = form.label.trim().to_string();
+    if label.is_empty() {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Le libellé est obligatoire").into_response();
+    }
+    if !is_valid_color(&form.color) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Couleur invalide (format #rrggbb)").into_response();
+    }
+    let category = Category {
+        id,
+        label,
+        color: form.color,
+        icon: form.icon.trim().to_string(),
+        position: form.position,
+    };
+    match state.categories.save(category) {
+        Ok(()) => (StatusCode::SEE_OTHER, [(LOCATION, ADMIN_PAGE)]).into_response(),
+        Err(e) => store_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct DeleteCategoryForm {
+    csrf_token: String,
+}
+
+/// Supprime une catégorie qu'aucune recommandation n'utilise, quel que soit son état de modération.
+async fn delete_category(
+    State(state): State<AppState>,
+    Admin(user): Admin,
+    UrlPath(id): UrlPath<String>,
+    Form(form): Form<DeleteCategoryForm>,
+) -> Response {
+    if let Err(e) = user.check_csrf(&form.csrf_token) {
+        return e.into_response();
+    }
+    if state.categories.get(&id).is_none() {
+        return (StatusCode::NOT_FOUND, "Catégorie inconnue").into_response();
+    }
+    let mut used = 0;
+    for show in state.shows.iter() {
+        match show.store.all() {
+            Ok(map) => used += map.values().flatten().filter(|rec| rec.type_media == id).count(),
+            Err(e) => return store_error(e),
+        }
+    }
+    if used > 0 {
+        return (
+            StatusCode::CONFLICT,
+            format!("{} recommandation(s) utilisent cette catégorie : changez-les avant de la supprimer", used),
+        ).into_response();
+    }
+    match state.categories.delete(&id) {
+        Ok(()) => (StatusCode::SEE_OTHER, [(LOCATION, ADMIN_PAGE)]).into_response(),
+        Err(e) => store_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_text_types_map_to_the_starting_categories() {
+        assert_eq!(legacy_id("Chaîne YouTube"), Some("chaine"));
+        assert_eq!(legacy_id(" Série TV "), Some("serie"));
+        assert_eq!(legacy_id("Film d'animation"), Some("film"));
+        assert_eq!(legacy_id("Jeu vidéo"), Some("jeu"));
+        assert_eq!(legacy_id("Compte Instagram"), Some("compte"));
+        assert_eq!(legacy_id("Podcast !"), Some("autre"));
+        assert_eq!(legacy_id(""), Some("autre"));
+    }
+
+    #[test]
+    fn ids_are_kept_even_when_unknown() {
+        assert_eq!(legacy_id("livre"), None);
+        assert_eq!(legacy_id("podcast"), None);
+        assert_eq!(legacy_id("bande-dessinee"), None);
+    }
+
+    #[test]
+    fn mapped_values_are_stable() {
+        for text in ["Chaîne YouTube", "Série", "Podcast !", "LIVRE"] {
+            let id = legacy_id(text).unwrap();
+            assert_eq!(legacy_id(id), None, "{} → {}", text, id);
+        }
+    }
+}
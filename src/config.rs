@@ -0,0 +1,422 @@
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use axum::http::HeaderName;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use crate::auth::{self, Account};
+use crate::show::{self, Show};
+
+/// Fichier de configuration lu par défaut s'il existe dans le répertoire courant.
+const DEFAULT_CONFIG_FILE: &str = "pap.toml";
+
+const DEFAULT_FEED_URL: &str = "https://feeds.acast.com/public/shows/portes-a-potes-pap";
+const DEFAULT_BIND: &str = "127.0.0.1:3000";
+const DEFAULT_DATA_FILE: &str = "recommandations.json";
+const DEFAULT_FEED_REFRESH_SECS: u64 = 600;
+/// En deçà, on interroge l'hébergeur du flux plus que de raison.
+const MIN_FEED_REFRESH_SECS: u64 = 30;
+const DEFAULT_DATABASE: &str = "pap.sqlite3";
+const DEFAULT_BACKUPS: usize = 10;
+const DEFAULT_HOSTS_FILE: &str = "chroniqueurs.json";
+const DEFAULT_CATEGORIES_FILE: &str = "categories.json";
+const DEFAULT_POSITIONS_FILE: &str = "positions.json";
+
+/// Configuration résolue du serveur.
+///
+/// Ordre de priorité : options de ligne de commande, puis variables d'environnement,
+/// puis fichier TOML, puis valeurs par défaut.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub command: Command,
+    pub bind: SocketAddr,
+    pub feed_refresh: Duration,
+    pub storage: StorageKind,
+    /// Sauvegardes horodatées conservées pour chaque fichier JSON (stockage `json`, 0 : aucune).
+    pub backups: usize,
+    /// Base SQLite partagée par les émissions (stockage `sqlite`).
+    pub database: PathBuf,
+    /// Registre des chroniqueurs, commun à toutes les émissions.
+    pub hosts_file: PathBuf,
+    /// Catégories des recommandations, communes à toutes les émissions.
+    pub categories_file: PathBuf,
+    /// Positions d'écoute des comptes connectés.
+    pub positions_file: PathBuf,
+    /// En-tête où le proxy inverse place l'adresse du visiteur (`X-Forwarded-For`, `X-Real-IP`) ;
+    /// sans proxy, l'adresse de la connexion fait foi.
+    pub client_ip_header: Option<HeaderName>,
+    /// Cookie de session marqué `Secure`, à activer quand le site est servi en HTTPS.
+    pub secure_cookies: bool,
+    /// Émissions servies, dans l'ordre de l'index ; jamais vide.
+    pub shows: Vec<Show>,
+    /// Comptes autorisés à modifier les recommandations.
+    pub users: Vec<Account>,
+}
+
+/// Emplacement des recommandations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// Un fichier JSON par émission (`data_file`)
+    Json,
+    /// Une base SQLite commune (`database`)
+    Sqlite,
+}
+
+#[derive(Subcommand, Debug, Clone, Default)]
+pub enum Command {
+    /// Lance le serveur (par défaut)
+    #[default]
+    Serve,
+    /// Remplace les recommandations d'une émission par le contenu d'un fichier JSON
+    Import {
+        /// Slug de l'émission (par défaut, la première configurée)
+        #[arg(long)]
+        show: Option<String>,
+        /// Fichier à importer (par défaut, le `data_file` de l'émission)
+        file: Option<PathBuf>,
+    },
+    /// Écrit les recommandations d'une émission dans un fichier JSON
+    Export {
+        /// Slug de l'émission (par défaut, la première configurée)
+        #[arg(long)]
+        show: Option<String>,
+        /// Fichier de destination
+        file: PathBuf,
+    },
+    /// Affiche le hash d'un mot de passe lu sur l'entrée standard, à recopier dans `[[users]]`
+    HashPassword,
+}
+
+/// Options de ligne de commande (chacune peut aussi venir de l'environnement).
+#[derive(Parser, Debug)]
+#[command(version, about = "Site du podcast Portes à Potes")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Fichier de configuration TOML
+    #[arg(long, short, env = "PAP_CONFIG")]
+    config: Option<PathBuf>,
+    /// URL du flux RSS du podcast (émission unique, sans `[[shows]]`)
+    #[arg(long, env = "PAP_FEED_URL")]
+    feed_url: Option<String>,
+    /// Adresse d'écoute du serveur (ex. 0.0.0.0:8080)
+    #[arg(long, env = "PAP_BIND")]
+    bind: Option<SocketAddr>,
+    /// Fichier JSON des recommandations (émission unique, sans `[[shows]]`)
+    #[arg(long, env = "PAP_DATA_FILE")]
+    data_file: Option<PathBuf>,
+    /// Intervalle de rafraîchissement du flux, en secondes
+    #[arg(long, env = "PAP_FEED_REFRESH_SECS")]
+    feed_refresh_secs: Option<u64>,
+    /// Stockage des recommandations
+    #[arg(long, env = "PAP_STORAGE")]
+    storage: Option<StorageKind>,
+    /// Nombre de sauvegardes conservées par fichier JSON (stockage json, 0 pour désactiver)
+    #[arg(long, env = "PAP_BACKUPS")]
+    backups: Option<usize>,
+    /// Base SQLite (stockage sqlite)
+    #[arg(long, env = "PAP_DATABASE")]
+    database: Option<PathBuf>,
+    /// Fichier JSON du registre des chroniqueurs
+    #[arg(long, env = "PAP_HOSTS_FILE")]
+    hosts_file: Option<PathBuf>,
+    /// Fichier JSON des catégories de recommandations
+    #[arg(long, env = "PAP_CATEGORIES_FILE")]
+    categories_file: Option<PathBuf>,
+    /// Fichier JSON des positions d'écoute des comptes
+    #[arg(long, env = "PAP_POSITIONS_FILE")]
+    positions_file: Option<PathBuf>,
+    /// En-tête HTTP portant l'adresse du visiteur derrière un proxy inverse (ex. X-Forwarded-For)
+    #[arg(long, env = "PAP_CLIENT_IP_HEADER")]
+    client_ip_header: Option<String>,
+    /// Cookie de session réservé au HTTPS (site servi en HTTPS)
+    #[arg(long, env = "PAP_SECURE_COOKIES", num_args = 0..=1, default_missing_value = "true")]
+    secure_cookies: Option<bool>,
+}
+
+/// Contenu du fichier TOML ; toutes les clés sont optionnelles.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    feed_url: Option<String>,
+    bind: Option<SocketAddr>,
+    data_file: Option<PathBuf>,
+    feed_refresh_secs: Option<u64>,
+    storage: Option<StorageKind>,
+    backups: Option<usize>,
+    database: Option<PathBuf>,
+    hosts_file: Option<PathBuf>,
+    categories_file: Option<PathBuf>,
+    positions_file: Option<PathBuf>,
+    client_ip_header: Option<String>,
+    secure_cookies: Option<bool>,
+    shows: Vec<Show>,
+    users: Vec<Account>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Toml(PathBuf, toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "lecture de {} impossible : {}", path.display(), e),
+            ConfigError::Toml(path, e) => write!(f, "{} invalide : {}", path.display(), e),
+            ConfigError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Résout la configuration à partir des arguments du processus.
+    pub fn load() -> Result<Config, ConfigError> {
+        Config::resolve(Cli::parse())
+    }
+
+    fn resolve(cli: Cli) -> Result<Config, ConfigError> {
+        // Un fichier donné explicitement doit exister ; le fichier par défaut est facultatif
+        let file = match &cli.config {
+            Some(path) => read_file(path)?,
+            None => {
+                let path = PathBuf::from(DEFAULT_CONFIG_FILE);
+                if path.exists() { read_file(&path)? } else { FileConfig::default() }
+            }
+        };
+        let feed_url = cli.feed_url.or(file.feed_url);
+        let data_file = cli.data_file.or(file.data_file);
+        let shows = if file.shows.is_empty() {
+            vec![Show::portes_a_potes(
+                feed_url.unwrap_or_else(|| DEFAULT_FEED_URL.to_string()),
+                data_file.unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_FILE)),
+            )]
+        } else {
+            // Avec plusieurs émissions, le flux et le fichier se règlent dans chaque `[[shows]]`
+            if feed_url.is_some() || data_file.is_some() {
+                return Err(ConfigError::Invalid(
+                    "feed_url et data_file ne s'appliquent qu'à l'émission par défaut : \
+                     renseignez-les dans chaque [[shows]]".to_string(),
+                ));
+            }
+            file.shows
+        };
+        validate_shows(&shows)?;
+        validate_users(&file.users)?;
+        let feed_refresh_secs = cli.feed_refresh_secs
+            .or(file.feed_refresh_secs)
+            .unwrap_or(DEFAULT_FEED_REFRESH_SECS);
+        if feed_refresh_secs < MIN_FEED_REFRESH_SECS {
+            return Err(ConfigError::Invalid(format!(
+                "feed_refresh_secs vaut {} : le flux ne peut pas être rafraîchi plus d'une fois toutes les {} secondes",
+                feed_refresh_secs, MIN_FEED_REFRESH_SECS
+            )));
+        }
+        let client_ip_header = match cli.client_ip_header.or(file.client_ip_header) {
+            Some(name) => Some(HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| {
+                ConfigError::Invalid(format!("client_ip_header : « {} » n'est pas un nom d'en-tête HTTP", name))
+            })?),
+            None => None,
+        };
+        Ok(Config {
+            command: cli.command.unwrap_or_default(),
+            bind: cli.bind
+                .or(file.bind)
+                .unwrap_or_else(|| DEFAULT_BIND.parse().unwrap()),
+            feed_refresh: Duration::from_secs(feed_refresh_secs),
+            storage: cli.storage.or(file.storage).unwrap_or(StorageKind::Json),
+            backups: cli.backups.or(file.backups).unwrap_or(DEFAULT_BACKUPS),
+            database: cli.database
+                .or(file.database)
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_DATABASE)),
+            hosts_file: cli.hosts_file
+                .or(file.hosts_file)
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_HOSTS_FILE)),
+            categories_file: cli.categories_file
+                .or(file.categories_file)
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_CATEGORIES_FILE)),
+            positions_file: cli.positions_file
+                .or(file.positions_file)
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_POSITIONS_FILE)),
+            client_ip_header,
+            secure_cookies: cli.secure_cookies.or(file.secure_cookies).unwrap_or(false),
+            shows,
+            users: file.users,
+        })
+    }
+}
+
+/// Vérifie que les slugs sont utilisables dans les URL et que chaque émission a ses propres données.
+fn validate_shows(shows: &[Show]) -> Result<(), ConfigError> {
+    for (i, s) in shows.iter().enumerate() {
+        if !show::is_valid_slug(&s.slug) {
+            return Err(ConfigError::Invalid(format!(
+                "slug d'émission invalide « {} » (minuscules, chiffres et tirets uniquement)", s.slug
+            )));
+        }
+        for other in &shows[..i] {
+            if other.slug == s.slug {
+                return Err(ConfigError::Invalid(format!("slug d'émission en double : {}", s.slug)));
+            }
+            if other.data_file == s.data_file {
+                return Err(ConfigError::Invalid(format!(
+                    "les émissions {} et {} partagent le fichier {}", other.slug, s.slug, s.data_file.display()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_users(users: &[Account]) -> Result<(), ConfigError> {
+    for (i, user) in users.iter().enumerate() {
+        if users[..i].iter().any(|other| other.login == user.login) {
+            return Err(ConfigError::Invalid(format!("identifiant en double : {}", user.login)));
+        }
+        if !auth::is_valid_hash(&user.password_hash) {
+            return Err(ConfigError::Invalid(format!(
+                "hash de mot de passe invalide pour {} (générez-le avec `pap hash-password`)", user.login
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn read_file(path: &Path) -> Result<FileConfig, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+    toml::from_str(&text).map_err(|e| ConfigError::Toml(path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Les variables d'environnement sont communes au processus : un test à la fois.
+    static ENV: Mutex<()> = Mutex::new(());
+
+    const ENV_VARS: &[&str] = &[
+        "PAP_CONFIG", "PAP_FEED_URL", "PAP_BIND", "PAP_DATA_FILE", "PAP_FEED_REFRESH_SECS", "PAP_STORAGE",
+        "PAP_BACKUPS", "PAP_DATABASE", "PAP_HOSTS_FILE", "PAP_CATEGORIES_FILE", "PAP_POSITIONS_FILE",
+        "PAP_CLIENT_IP_HEADER", "PAP_SECURE_COOKIES",
+    ];
+
+    /// Configuration obtenue avec ce fichier TOML, ces variables d'environnement et ces arguments.
+    fn resolve(toml: &str, env: &[(&str, &str)], args: &[&str]) -> Result<Config, ConfigError> {
+        let _guard = ENV.lock().unwrap_or_else(|e| e.into_inner());
+        let path = std::env::temp_dir().join(format!("pap-config-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, toml).unwrap();
+        // SAFETY : les seuls tests qui lisent l'environnement passent par ce verrou
+        unsafe {
+            for name in ENV_VARS {
+                std::env::remove_var(name);
+            }
+            for (name, value) in env {
+                std::env::set_var(name, value);
+            }
+        }
+        let mut argv = vec!["pap", "--config", path.to_str().unwrap()];
+        argv.extend_from_slice(args);
+        let config = Config::resolve(Cli::try_parse_from(argv).unwrap());
+        unsafe {
+            for (name, _) in env {
+                std::env::remove_var(name);
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+        config
+    }
+
+    fn invalid(toml: &str, env: &[(&str, &str)], args: &[&str]) -> String {
+        match resolve(toml, env, args) {
+            Err(ConfigError::Invalid(message)) => message,
+            other => panic!("configuration acceptée ou autre erreur : {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults_apply_without_settings() {
+        let config = resolve("", &[], &[]).unwrap();
+        assert_eq!(config.bind, DEFAULT_BIND.parse().unwrap());
+        assert_eq!(config.feed_refresh, Duration::from_secs(DEFAULT_FEED_REFRESH_SECS));
+        assert_eq!(config.storage, StorageKind::Json);
+        assert_eq!(config.backups, DEFAULT_BACKUPS);
+        assert_eq!(config.hosts_file, PathBuf::from(DEFAULT_HOSTS_FILE));
+        assert!(config.client_ip_header.is_none());
+        assert_eq!(config.shows.len(), 1);
+        assert_eq!(config.shows[0].feed_url, DEFAULT_FEED_URL);
+    }
+
+    #[test]
+    fn command_line_then_environment_then_file() {
+        let toml = r#"
+            bind = "127.0.0.1:4001"
+            backups = 3
+            feed_refresh_secs = 120
+            hosts_file = "fichier.json"
+            database = "fichier.sqlite3"
+        "#;
+        let env = [("PAP_BIND", "127.0.0.1:4002"), ("PAP_BACKUPS", "4"), ("PAP_DATABASE", "env.sqlite3")];
+        let config = resolve(toml, &env, &["--bind", "127.0.0.1:4003", "--storage", "sqlite"]).unwrap();
+        assert_eq!(config.bind, "127.0.0.1:4003".parse().unwrap());
+        assert_eq!(config.storage, StorageKind::Sqlite);
+        assert_eq!(config.backups, 4);
+        assert_eq!(config.database, PathBuf::from("env.sqlite3"));
+        assert_eq!(config.feed_refresh, Duration::from_secs(120));
+        assert_eq!(config.hosts_file, PathBuf::from("fichier.json"));
+        assert_eq!(config.categories_file, PathBuf::from(DEFAULT_CATEGORIES_FILE));
+    }
+
+    #[test]
+    fn feed_refresh_below_the_minimum_is_rejected() {
+        assert!(invalid("feed_refresh_secs = 0", &[], &[]).contains("feed_refresh_secs"));
+        invalid("", &[("PAP_FEED_REFRESH_SECS", "29")], &[]);
+        invalid("feed_refresh_secs = 600", &[], &["--feed-refresh-secs", "1"]);
+        let config = resolve("feed_refresh_secs = 0", &[], &["--feed-refresh-secs", "30"]).unwrap();
+        assert_eq!(config.feed_refresh, Duration::from_secs(MIN_FEED_REFRESH_SECS));
+    }
+
+    #[test]
+    fn secure_cookies_flag() {
+        assert!(!resolve("", &[], &[]).unwrap().secure_cookies);
+        assert!(resolve("secure_cookies = true", &[], &[]).unwrap().secure_cookies);
+        assert!(resolve("", &[], &["--secure-cookies"]).unwrap().secure_cookies);
+        assert!(!resolve("secure_cookies = true", &[("PAP_SECURE_COOKIES", "false")], &[]).unwrap().secure_cookies);
+        assert!(matches!(resolve("secure_cookies = \"oui\"", &[], &[]), Err(ConfigError::Toml(..))));
+    }
+
+    #[test]
+    fn client_ip_header_must_be_a_header_name() {
+        invalid(r#"client_ip_header = "pas un en-tête""#, &[], &[]);
+        let config = resolve("", &[("PAP_CLIENT_IP_HEADER", " X-Forwarded-For ")], &[]).unwrap();
+        assert_eq!(config.client_ip_header, Some(HeaderName::from_static("x-forwarded-for")));
+    }
+
+    #[test]
+    fn shows_must_be_distinct_and_own_their_settings() {
+        let show = |slug: &str, file: &str| format!(
+            "[[shows]]\nslug = \"{}\"\nname = \"Émission\"\nfeed_url = \"http://exemple.fr/rss\"\ndata_file = \"{}\"\n",
+            slug, file
+        );
+        assert_eq!(resolve(&show("pap", "a.json"), &[], &[]).unwrap().shows[0].slug, "pap");
+        invalid(&format!("{}{}", show("pap", "a.json"), show("pap", "b.json")), &[], &[]);
+        invalid(&format!("{}{}", show("pap", "a.json"), show("autre", "a.json")), &[], &[]);
+        invalid(&show("Pas Valide", "a.json"), &[], &[]);
+        invalid(&show("pap", "a.json"), &[], &["--feed-url", "http://exemple.fr/autre"]);
+    }
+
+    #[test]
+    fn unreadable_files_are_errors() {
+        assert!(matches!(resolve("inconnue = 1", &[], &[]), Err(ConfigError::Toml(..))));
+        assert!(matches!(resolve("bind = \"pas une adresse\"", &[], &[]), Err(ConfigError::Toml(..))));
+        let _guard = ENV.lock().unwrap_or_else(|e| e.into_inner());
+        let cli = Cli::try_parse_from(["pap", "--config", "/nonexistent/pap.toml"]).unwrap();
+        assert!(matches!(Config::resolve(cli), Err(ConfigError::Io(..))));
+    }
+}
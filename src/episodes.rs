@@ -0,0 +1,311 @@
+//! Page d'un épisode (`/shows/{show}/episodes/{slug}`, ou `/episodes/{slug}` quand une seule
+//! émission est servie) : description, lecteur, recommandations, intervenants, chapitres,
+//! transcription consultable et navigation vers les épisodes voisins.
+//!
+//! Le slug (`3f9a2c1b-s2e6-bienvenue-chez-mcdonalds`) ne dépend du titre que pour sa fin : une adresse
+//! dont le titre a changé, ou l'ancienne adresse par guid, redirige vers l'adresse actuelle.
+
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use crate::auth::CurrentUser;
+use crate::categories;
+use crate::feed::{self, Episode, Person};
+use crate::podcast::{self, Chapter, Cue};
+use crate::store::{Recommendation, Status};
+use crate::{AppState, ShowState, feed_unavailable_page, show_not_found, store_error};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/shows/{show}/episodes/{slug}", get(show_episode_page))
+        .route("/episodes/{slug}", get(root_episode_page))
+}
+
+#[derive(Deserialize)]
+struct EpisodeQuery {
+    /// Émission des anciennes adresses `/episodes/{slug}?show=slug`.
+    show: Option<String>,
+}
+
+/// Adresse de la page d'un épisode, sous celle de son émission.
+pub fn episode_path(show: &ShowState, ep: &Episode) -> String {
+    format!("{}/episodes/{}", show.page_path().trim_end_matches('/'), ep.slug)
+}
+
+/// Bouton qui lance le lecteur à `seconds` (voir `PLAYER_JS`) ; simple texte sans fichier audio.
+fn seek_button(ep: &Episode, seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u32;
+    if ep.audio.is_some() {
+        format!(
+            "<button class='timestamp-chip' data-ep='{}' data-t='{}' style='background:none;border:none;color:#4a90e2;cursor:pointer;font-family:monospace;padding:0;'>{}</button>",
+            htmlescape::encode_attribute(&ep.guid), seconds, feed::format_clock(seconds)
+        )
+    } else {
+        format!("<span style='color:#888;font-family:monospace;'>{}</span>", feed::format_clock(seconds))
+    }
+}
+
+/// Intervenants du flux ; ceux qui figurent au registre renvoient vers leur profil.
+fn persons_html(state: &AppState, persons: &[Person]) -> String {
+    if persons.is_empty() {
+        return String::new();
+    }
+    let items: String = persons.iter().map(|person| {
+        let role = match person.role.as_deref() {
+            None | Some("host") => "chroniqueur",
+            Some("guest") => "invité",
+            Some(other) => other,
+        };
+        let name = match state.hosts.find(&person.name) {
+            Some(host) => format!(
+                "<a href='/chroniqueurs/{}'>{}</a>",
+                htmlescape::encode_attribute(&host.id), htmlescape::encode_minimal(&host.name)
+            ),
+            None => match &person.href {
+                Some(href) => format!(
+                    "<a href='{}' target='_blank' rel='noopener'>{}</a>",
+                    htmlescape::encode_attribute(href), htmlescape::encode_minimal(&person.name)
+                ),
+                None => htmlescape::encode_minimal(&person.name),
+            },
+        };
+        let img = person.img.as_deref().map(|url| format!(
+            "<img src='{}' alt='' style='width:32px;height:32px;border-radius:50%;object-fit:cover;'>",
+            htmlescape::encode_attribute(url)
+        )).unwrap_or_default();
+        format!(
+            "<li style='display:flex;align-items:center;gap:0.6em;margin-bottom:0.4em;'>{} {} <span style='color:#888;'>({})</span></li>",
+            img, name, htmlescape::encode_minimal(role)
+        )
+    }).collect();
+    format!("<h2>Intervenants</h2><ul style='list-style:none;padding:0;'>{}</ul>", items)
+}
+
+/// Recommandations publiées de l'épisode, en lecture seule (on les modifie depuis la page de l'émission).
+fn recommendations_html(state: &AppState, ep: &Episode, recs: &[Recommendation]) -> String {
+    if recs.is_empty() {
+        return String::new();
+    }
+    let items: String = recs.iter().map(|rec| {
+        let titre = match &rec.lien {
+            Some(lien) => format!(
+                "<a href='{}' target='_blank' rel='noopener'>{}</a>",
+                htmlescape::encode_attribute(lien), htmlescape::encode_minimal(&rec.titre)
+            ),
+            None => htmlescape::encode_minimal(&rec.titre),
+        };
+        let chroniqueurs: Vec<String> = rec.chroniqueurs.iter().map(|id| format!(
+            "<a href='/chroniqueurs/{}' style='color:inherit;'>{}</a>",
+            htmlescape::encode_attribute(id),
+            htmlescape::encode_minimal(&state.hosts.display_name(id))
+        )).collect();
+        format!(
+            "<li style='background:#eaf6fb;border-radius:14px;box-shadow:0 2px 8px #0001;padding:0.9em 1.2em;margin-bottom:0.8em;list-style:none;'>\
+                <div style='display:flex;justify-content:space-between;align-items:center;gap:1em;'><b>{titre}</b><span style='display:flex;gap:0.5em;'>{timestamp}{badge}</span></div>\
+                <div style='color:#9147ff;font-style:italic;margin-top:0.3em;'>{chroniqueurs}</div>\
+                <div style='margin-top:0.3em;'>{description}</div>\
+            </li>",
+            titre = titre,
+            timestamp = rec.timestamp.map(|t| crate::timestamp_chip(ep, t)).unwrap_or_default(),
+            badge = categories::badge(&state.categories.display(&rec.type_media)),
+            chroniqueurs = crate::chroniqueurs_text(&chroniqueurs),
+            description = htmlescape::encode_minimal(&rec.description),
+        )
+    }).collect();
+    format!("<h2>Recommandations</h2><ul style='padding:0;'>{}</ul>", items)
+}
+
+/// Liens vers l'épisode précédent (plus ancien) et suivant (plus récent) du flux.
+fn neighbours_html(show: &ShowState, episodes: &[Episode], index: usize) -> String {
+    let link = |ep: &Episode, label: &str| format!(
+        "<a href='{}' style='max-width:48%;'>{}</a>",
+        htmlescape::encode_attribute(&episode_path(show, ep)), label.replace("{}", &htmlescape::encode_minimal(&ep.title))
+    );
+    // Le flux liste les épisodes du plus récent au plus ancien
+    let previous = episodes.get(index + 1).map(|ep| link(ep, "&larr; {}")).unwrap_or_default();
+    let next = index.checked_sub(1).and_then(|i| episodes.get(i)).map(|ep| link(ep, "{} &rarr;")).unwrap_or_default();
+    if previous.is_empty() && next.is_empty() {
+        return String::new();
+    }
+    format!(
+        "<nav class='episode-nav' style='display:flex;justify-content:space-between;gap:1em;margin-top:2em;padding-top:1em;border-top:1px solid #ddd;'><span>{}</span><span style='text-align:right;'>{}</span></nav>",
+        previous, next
+    )
+}
+
+fn chapters_html(ep: &Episode, chapters: &[Chapter]) -> String {
+    let items: String = chapters.iter().map(|chapter| {
+        let title = match &chapter.url {
+            Some(url) => format!(
+                "<a href='{}' target='_blank' rel='noopener'>{}</a>",
+                htmlescape::encode_attribute(url), htmlescape::encode_minimal(&chapter.title)
+            ),
+            None => htmlescape::encode_minimal(&chapter.title),
+        };
+        format!("<li style='margin-bottom:0.3em;'>{} {}</li>", seek_button(ep, chapter.start), title)
+    }).collect();
+    format!("<h2>Chapitres</h2><ol style='padding-left:1.2em;'>{}</ol>", items)
+}
+
+fn transcript_html(ep: &Episode, cues: &[Cue]) -> String {
+    let lines: String = cues.iter().map(|cue| format!(
+        "<p class='cue' style='margin:0.3em 0;'>{} {}<span class='cue-text'>{}</span></p>",
+        seek_button(ep, cue.start),
+        cue.speaker.as_deref().map(|s| format!("<b>{} :</b> ", htmlescape::encode_minimal(s))).unwrap_or_default(),
+        htmlescape::encode_minimal(&cue.text),
+    )).collect();
+    format!(
+        "<h2>Transcription</h2>\
+        <input id='transcript-search' type='search' placeholder='Rechercher dans la transcription...' aria-label='Rechercher dans la transcription' style='width:100%;padding:0.5em 0.8em;border-radius:2em;border:1px solid #ccc;box-sizing:border-box;'>\
+        <p id='transcript-count' style='color:#888;font-size:0.9em;'></p>\
+        <div id='transcript' style='max-height:28em;overflow-y:auto;background:#fff;border-radius:14px;padding:0.8em 1.2em;box-shadow:0 2px 8px #0001;'>{}</div>",
+        lines
+    )
+}
+
+/// Filtre les répliques de la transcription, sans tenir compte des accents ni de la casse.
+const TRANSCRIPT_JS: &str = r#"
+<script>
+document.addEventListener('DOMContentLoaded', function() {
+    var input = document.getElementById('transcript-search');
+    if(!input) return;
+    var count = document.getElementById('transcript-count');
+    function fold(text) { return text.normalize('NFD').replace(/[\u0300-\u036f]/g, '').toLowerCase(); }
+    input.addEventListener('input', function() {
+        var query = fold(input.value.trim());
+        var shown = 0;
+        document.querySelectorAll('#transcript .cue').forEach(function(cue) {
+            var match = !query || fold(cue.textContent).indexOf(query) !== -1;
+            cue.style.display = match ? '' : 'none';
+            if(match) shown++;
+        });
+        count.textContent = query ? shown + ' passage(s) trouvé(s)' : '';
+    });
+});
+</script>
+"#;
+
+async fn show_episode_page(
+    State(state): State<AppState>,
+    user: Option<CurrentUser>,
+    UrlPath((show_slug, slug)): UrlPath<(String, String)>,
+) -> Response {
+    let Some(show) = state.show(Some(&show_slug)) else {
+        return show_not_found();
+    };
+    let requested = format!("/shows/{}/episodes/{}", show_slug, slug);
+    episode_page(&state, user, show, &slug, &requested).await
+}
+
+/// Avec plusieurs émissions, `/episodes/{slug}` ne sert plus qu'à rediriger les anciennes
+/// adresses qui précisent l'émission (`?show=slug`).
+async fn root_episode_page(
+    State(state): State<AppState>,
+    user: Option<CurrentUser>,
+    UrlPath(slug): UrlPath<String>,
+    Query(query): Query<EpisodeQuery>,
+) -> Response {
+    let show = match &query.show {
+        Some(show_slug) => state.show(Some(show_slug)),
+        None if state.shows.len() == 1 => state.shows.first(),
+        None => None,
+    };
+    let Some(show) = show else {
+        return show_not_found();
+    };
+    let requested = match query.show {
+        Some(_) => String::new(),
+        None => format!("/episodes/{}", slug),
+    };
+    episode_page(&state, user, show, &slug, &requested).await
+}
+
+/// Page de l'épisode `slug` ; toute autre adresse que `episode_path` est redirigée vers celle-ci.
+async fn episode_page(state: &AppState, user: Option<CurrentUser>, show: &ShowState, slug: &str, requested: &str) -> Response {
+    let episodes = show.feed.episodes();
+    if episodes.is_empty() {
+        return feed_unavailable_page(show);
+    }
+    let Some(ep) = feed::find_episode(&episodes, slug) else {
+        return (StatusCode::NOT_FOUND, "Épisode inconnu").into_response();
+    };
+    let path = episode_path(show, ep);
+    if path != requested {
+        return (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, path)]).into_response();
+    }
+    let index = episodes.iter().position(|other| std::ptr::eq(other, ep)).unwrap_or(0);
+    let mut recs = match show.store.all() {
+        Ok(mut map) => map.remove(&ep.guid).unwrap_or_default(),
+        Err(e) => return store_error(e),
+    };
+    recs.retain(|rec| rec.status == Status::Approved);
+    let image = if ep.image_url.is_empty() || ep.image_url.contains("placeholder.com") {
+        String::new()
+    } else {
+        format!(
+            "<img src='{}' alt='' style='width:160px;height:160px;border-radius:14px;object-fit:contain;background:#fff;box-shadow:0 2px 8px #0002;float:right;margin:0 0 1em 1em;'>",
+            htmlescape::encode_attribute(&ep.image_url)
+        )
+    };
+    // Téléchargés par la tâche de fond (`podcast::spawn_prefetch`) : absents, ils sont encore en
+    // route ou leur hébergeur ne répond pas
+    let chapters = match ep.chapters_url.as_deref().map(|url| state.extras.chapters(url)) {
+        Some(Some(chapters)) if !chapters.is_empty() => chapters_html(ep, &chapters),
+        Some(None) => "<h2>Chapitres</h2><p style='color:#888;'>Chapitres indisponibles pour le moment.</p>".to_string(),
+        _ => String::new(),
+    };
+    let transcript = match podcast::preferred_transcript(&ep.transcripts).map(|link| state.extras.transcript(link)) {
+        Some(Some(cues)) if !cues.is_empty() => transcript_html(ep, &cues),
+        Some(None) => "<h2>Transcription</h2><p style='color:#888;'>Transcription indisponible pour le moment.</p>".to_string(),
+        _ => String::new(),
+    };
+    Html(format!(
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>{title}</title>
+        </head>
+        <body data-show="{slug}" data-csrf="{csrf}" style="font-family:sans-serif;margin:0;background:#f3f6fa;">
+            <main style="max-width:760px;margin:0 auto;padding:2em 1em;">
+                <a href="{back}">&larr; {show_name}</a>
+                {image}
+                <h1>{title}</h1>
+                <div style="color:#888;">{date}{meta}</div>
+                {player}
+                <div class="desc" style="margin-top:1em;clear:both;">{description}</div>
+                {recommendations}
+                {persons}
+                {chapters}
+                {transcript}
+                {neighbours}
+            </main>
+            {mini_player}
+            {player_js}
+            {transcript_js}
+        </body>
+        </html>"#,
+        image = image,
+        title = htmlescape::encode_minimal(&ep.title),
+        slug = show.show.slug,
+        csrf = user.as_ref().map(|u| u.csrf_token.as_str()).unwrap_or(""),
+        back = show.page_path(),
+        show_name = htmlescape::encode_minimal(&show.show.name),
+        date = htmlescape::encode_minimal(&ep.date),
+        meta = crate::episode_meta(ep),
+        player = crate::inline_player(ep),
+        description = crate::episode_description(ep),
+        recommendations = recommendations_html(state, ep, &recs),
+        neighbours = neighbours_html(show, &episodes, index),
+        persons = persons_html(state, &ep.persons),
+        chapters = chapters,
+        transcript = transcript,
+        mini_player = if ep.audio.is_some() { crate::MINI_PLAYER } else { "" },
+        player_js = if ep.audio.is_some() { crate::PLAYER_JS } else { "" },
+        transcript_js = TRANSCRIPT_JS,
+    )).into_response()
+}
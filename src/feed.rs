@@ -0,0 +1,826 @@
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::Serialize;
+use crate::hosts::fold_accents;
+use crate::store::is_web_url;
+
+/// Délai maximal d'une requête vers le flux, pour ne pas bloquer une page si Acast ne répond pas.
+const FETCH_TIMEOUT_SECS: u64 = 15;
+
+/// Délai entre deux tentatives tant qu'aucune copie du flux n'a pu être lue.
+const EMPTY_CACHE_RETRY_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Episode {
+    /// Identifiant stable : `<guid>`, à défaut `acast:episodeId`, à défaut le titre.
+    pub guid: String,
+    /// Partie de l'adresse de la page de l'épisode (`/shows/{show}/episodes/{slug}`, les anciennes
+    /// adresses `/episodes/{slug}` y redirigeant), voir [`find_episode`].
+    pub slug: String,
+    pub title: String,
+    pub date: String,
+    pub description: String,
+    pub image_url: String,
+    /// `itunes:season`, à défaut lue dans le titre.
+    pub season: Option<u32>,
+    /// `itunes:episode`, à défaut lu dans le titre.
+    pub number: Option<u32>,
+    pub episode_type: EpisodeType,
+    /// Fichier audio (`<enclosure>`).
+    pub audio: Option<Enclosure>,
+    /// Durée en secondes (`itunes:duration`).
+    pub duration: Option<u32>,
+    /// Page de l'épisode chez l'hébergeur (`<link>`).
+    pub link: Option<String>,
+    /// `itunes:explicit`.
+    pub explicit: bool,
+    /// `itunes:summary`, en général un texte brut plus court que la description.
+    pub summary: Option<String>,
+    /// Fichier JSON des chapitres (`podcast:chapters`).
+    pub chapters_url: Option<String>,
+    /// Transcriptions proposées (`podcast:transcript`), dans l'ordre du flux.
+    pub transcripts: Vec<TranscriptLink>,
+    /// Intervenants (`podcast:person`).
+    pub persons: Vec<Person>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptLink {
+    pub url: String,
+    /// Type MIME annoncé (`text/vtt`, `application/x-subrip`, `application/json`...).
+    pub mime_type: String,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Person {
+    pub name: String,
+    /// Rôle selon la taxonomie Podcasting 2.0 (`host`, `guest`...) ; `host` par défaut.
+    pub role: Option<String>,
+    pub img: Option<String>,
+    pub href: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Enclosure {
+    pub url: String,
+    /// Type MIME annoncé (`audio/mpeg`...).
+    pub mime_type: Option<String>,
+    /// Taille en octets.
+    pub length: Option<u64>,
+}
+
+/// `itunes:duration` : nombre de secondes, `MM:SS` ou `HH:MM:SS` (minutes et secondes de 0 à
+/// 59 après le premier champ).
+pub fn parse_duration(value: &str) -> Option<u32> {
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut total: u32 = 0;
+    for (i, part) in parts.iter().enumerate() {
+        let part = part.trim().parse::<u32>().ok()?;
+        if i > 0 && part > 59 {
+            return None;
+        }
+        total = total.checked_mul(60)?.checked_add(part)?;
+    }
+    Some(total)
+}
+
+/// Position dans un épisode : « 34:12 », « 1:02:03 ».
+pub fn format_clock(secs: u32) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Durée lisible : « 45 min », « 1 h 02 ».
+pub fn format_duration(secs: u32) -> String {
+    let minutes = secs / 60;
+    if minutes < 60 {
+        format!("{} min", minutes.max(1))
+    } else {
+        format!("{} h {:02}", minutes / 60, minutes % 60)
+    }
+}
+
+/// `itunes:episodeType` : les bandes-annonces et bonus sont affichés à part.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpisodeType {
+    #[default]
+    Full,
+    Trailer,
+    Bonus,
+}
+
+impl EpisodeType {
+    /// Valeur de `itunes:episodeType` ; sans elle, le titre trahit parfois une bande-annonce ou un bonus.
+    fn parse(value: &str, title: &str) -> EpisodeType {
+        match value.trim().to_lowercase().as_str() {
+            "full" => EpisodeType::Full,
+            "trailer" => EpisodeType::Trailer,
+            "bonus" => EpisodeType::Bonus,
+            _ => {
+                let title = title.to_lowercase();
+                if title.contains("bande-annonce") || title.contains("bande annonce") || title.contains("trailer") {
+                    EpisodeType::Trailer
+                } else if title.contains("bonus") {
+                    EpisodeType::Bonus
+                } else {
+                    EpisodeType::Full
+                }
+            }
+        }
+    }
+}
+
+/// Saison et numéro d'épisode écrits dans un titre : « PAP S2/E6 - ... », « S02E06 », « Saison 2 ».
+pub fn numbers_from_title(title: &str) -> (Option<u32>, Option<u32>) {
+    let chars: Vec<char> = title.to_lowercase().chars().collect();
+    // Nombre qui commence à `start` (espaces ignorés), et position qui le suit
+    let number_at = |start: usize| {
+        let start = start.min(chars.len());
+        let start = start + chars[start..].iter().take_while(|c| **c == ' ').count();
+        let len = chars[start..].iter().take_while(|c| c.is_ascii_digit()).count();
+        let number = chars[start..start + len].iter().collect::<String>().parse::<u32>().ok();
+        (number, start + len)
+    };
+    for i in 0..chars.len() {
+        if chars[i] != 's' || (i > 0 && chars[i - 1].is_alphanumeric()) {
+            continue;
+        }
+        let after = if chars[i..].starts_with(&['s', 'a', 'i', 's', 'o', 'n']) { i + 6 } else { i + 1 };
+        let (Some(season), next) = number_at(after) else {
+            continue;
+        };
+        let separators = chars[next..].iter().take_while(|c| matches!(c, ' ' | '/' | '-' | '.' | '_')).count();
+        let episode = match chars.get(next + separators) {
+            Some('e') => number_at(next + separators + 1).0,
+            _ => None,
+        };
+        return (Some(season), episode);
+    }
+    (None, None)
+}
+
+/// Longueur maximale de la partie du slug tirée du titre.
+const SLUG_TITLE_MAX: usize = 60;
+
+/// Longueur de la clé d'un slug, en chiffres hexadécimaux (doublée en cas de collision).
+const SLUG_KEY_LEN: usize = 8;
+
+/// Mots en minuscules sans accents, séparés par des tirets.
+fn slug_words(text: &str) -> String {
+    fold_accents(&text.to_lowercase())
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Empreinte FNV-1a du guid, brassée pour que des guid voisins (`ep-6`, `ep-7`) donnent des
+/// clés bien distinctes. Elle ne dépend ni de la version de Rust ni de la place de l'épisode
+/// dans le flux.
+fn guid_hash(guid: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in guid.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    // Étape finale de MurmurHash3
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^= hash >> 33;
+    format!("{:016x}", hash)
+}
+
+/// Clé d'un slug : ce qui précède le premier tiret.
+fn slug_key(slug: &str) -> &str {
+    slug.split('-').next().unwrap_or(slug)
+}
+
+/// Slugs des épisodes : `3f9a2c1b-s2e6-bienvenue-chez-mcdonalds`. La clé, tirée du guid, identifie
+/// l'épisode et ne change jamais ; le reste (numéro et titre) n'est là que pour la lisibilité.
+///
+/// En cas de collision, l'épisode le plus ancien garde la clé courte : l'arrivée d'un nouvel
+/// épisode ne change pas l'adresse des précédents.
+fn assign_slugs(episodes: &mut [Episode]) {
+    let mut taken: Vec<String> = Vec::new();
+    // Le flux liste les épisodes du plus récent au plus ancien
+    for ep in episodes.iter_mut().rev() {
+        let hash = guid_hash(&ep.guid);
+        let mut key = hash[..SLUG_KEY_LEN].to_string();
+        if taken.contains(&key) {
+            key = hash.clone();
+        }
+        // Guid en double dans le flux : rien ne les distingue, on les numérote
+        let mut n = 2;
+        while taken.contains(&key) {
+            key = format!("{}{}", hash, n);
+            n += 1;
+        }
+        taken.push(key.clone());
+        // « PAP S2/E6 - Bienvenue chez mcdonalds » : le numéro est repris sous la forme `s2e6`
+        let title = match ep.title.split_once(" - ") {
+            Some((prefix, rest)) if numbers_from_title(prefix).0.is_some() => rest,
+            _ => ep.title.as_str(),
+        };
+        let mut words = slug_words(title);
+        if words.len() > SLUG_TITLE_MAX {
+            let cut = words[..SLUG_TITLE_MAX].rfind('-').unwrap_or(SLUG_TITLE_MAX);
+            words.truncate(cut);
+        }
+        let numbers = match (ep.season, ep.number) {
+            (Some(season), Some(number)) => Some(format!("s{}e{}", season, number)),
+            _ => None,
+        };
+        ep.slug = [Some(key), numbers, Some(words).filter(|w| !w.is_empty())]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("-");
+    }
+}
+
+/// Épisode désigné par une adresse : son slug ou un slug dont seule la partie lisible a changé
+/// (même clé), à défaut son guid ou une adresse `s2e6-titre` des premières pages d'épisode.
+/// L'appelant redirige si `slug` diffère de l'adresse demandée.
+pub fn find_episode<'a>(episodes: &'a [Episode], requested: &str) -> Option<&'a Episode> {
+    episodes.iter().find(|ep| ep.slug == requested)
+        .or_else(|| episodes.iter().find(|ep| slug_key(&ep.slug) == slug_key(requested)))
+        .or_else(|| episodes.iter().find(|ep| ep.guid == requested))
+        .or_else(|| find_numbered(episodes, requested))
+}
+
+/// Ancienne adresse `s2e6-titre` : l'épisode de ce numéro, de préférence celui qui porte ce titre.
+fn find_numbered<'a>(episodes: &'a [Episode], requested: &str) -> Option<&'a Episode> {
+    let (season, rest) = requested.strip_prefix('s')?.split_once('e')?;
+    let season: u32 = season.parse().ok()?;
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let number: u32 = rest[..digits].parse().ok()?;
+    let title = rest[digits..].strip_prefix('-').unwrap_or_default();
+    let mut candidates = episodes.iter().filter(|ep| ep.season == Some(season) && ep.number == Some(number));
+    let first = candidates.clone().next()?;
+    Some(candidates.find(|ep| !title.is_empty() && ep.slug.ends_with(title)).unwrap_or(first))
+}
+
+/// Raisons pour lesquelles le flux n'a pas pu être lu.
+#[derive(Debug)]
+pub enum FeedError {
+    /// Requête impossible (DNS, connexion, délai dépassé, corps illisible...).
+    Network(reqwest::Error),
+    /// Le serveur a répondu avec un statut d'erreur.
+    Status(StatusCode),
+    /// Le document n'est pas un XML valide.
+    Xml(quick_xml::Error),
+    /// Le flux ne contient aucun épisode.
+    Empty,
+}
+
+impl fmt::Display for FeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedError::Network(e) => write!(f, "flux injoignable : {}", e),
+            FeedError::Status(status) => write!(f, "le flux a répondu {}", status),
+            FeedError::Xml(e) => write!(f, "flux XML invalide : {}", e),
+            FeedError::Empty => write!(f, "le flux ne contient aucun épisode"),
+        }
+    }
+}
+
+impl std::error::Error for FeedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FeedError::Network(e) => Some(e),
+            FeedError::Xml(e) => Some(e),
+            FeedError::Status(_) | FeedError::Empty => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for FeedError {
+    fn from(e: reqwest::Error) -> Self {
+        FeedError::Network(e)
+    }
+}
+
+impl From<quick_xml::Error> for FeedError {
+    fn from(e: quick_xml::Error) -> Self {
+        FeedError::Xml(e)
+    }
+}
+
+/// Dernière copie valide du flux, avec les validateurs HTTP pour les requêtes conditionnelles.
+#[derive(Default)]
+struct CachedFeed {
+    episodes: Arc<Vec<Episode>>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Message de la dernière erreur de rafraîchissement, effacé au prochain succès.
+    last_error: Option<String>,
+}
+
+/// Cache du flux RSS partagé entre les handlers et la tâche de rafraîchissement.
+pub struct FeedCache {
+    url: String,
+    client: reqwest::Client,
+    inner: RwLock<CachedFeed>,
+}
+
+impl FeedCache {
+    pub fn new(url: impl Into<String>) -> Self {
+        FeedCache {
+            url: url.into(),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+                .build()
+                .expect("client HTTP"),
+            inner: RwLock::new(CachedFeed::default()),
+        }
+    }
+
+    /// Épisodes de la dernière récupération réussie (vide tant que le flux n'a jamais été lu).
+    pub fn episodes(&self) -> Arc<Vec<Episode>> {
+        self.inner.read().unwrap().episodes.clone()
+    }
+
+    /// Erreur du dernier rafraîchissement, si celui-ci a échoué.
+    pub fn last_error(&self) -> Option<String> {
+        self.inner.read().unwrap().last_error.clone()
+    }
+
+    /// Relit le flux avec un GET conditionnel. En cas d'erreur, la copie en cache est conservée
+    /// et l'erreur est mémorisée pour l'affichage. Renvoie `true` si le contenu a changé.
+    pub async fn refresh(&self) -> Result<bool, FeedError> {
+        let result = self.fetch().await;
+        if let Err(e) = &result {
+            self.inner.write().unwrap().last_error = Some(e.to_string());
+        }
+        result
+    }
+
+    async fn fetch(&self) -> Result<bool, FeedError> {
+        let mut request = self.client.get(&self.url);
+        {
+            let cached = self.inner.read().unwrap();
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            self.inner.write().unwrap().last_error = None;
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            return Err(FeedError::Status(response.status()));
+        }
+        let header = |name| {
+            response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let xml = response.text().await?;
+        let episodes = parse_episodes(&xml)?;
+        let mut cached = self.inner.write().unwrap();
+        *cached = CachedFeed {
+            episodes: Arc::new(episodes),
+            etag,
+            last_modified,
+            last_error: None,
+        };
+        Ok(true)
+    }
+}
+
+type FeedAction = Box<dyn FnOnce(&[Episode]) + Send>;
+
+/// Action à lancer une seule fois, dès qu'une copie du flux a été lue.
+struct FirstLoad(Option<FeedAction>);
+
+impl FirstLoad {
+    fn run_if_loaded(&mut self, cache: &FeedCache) {
+        let episodes = cache.episodes();
+        if !episodes.is_empty()
+            && let Some(action) = self.0.take()
+        {
+            action(&episodes);
+        }
+    }
+}
+
+/// Lance la tâche de fond qui rafraîchit le cache à intervalle régulier. Tant que le cache est
+/// vide (flux injoignable au démarrage), elle réessaie plus souvent : les pages, elles, ne
+/// lisent jamais le flux elles-mêmes.
+///
+/// `on_first_load` (migrations qui ont besoin des épisodes) est lancée tout de suite si le
+/// chargement initial a réussi, sinon après le premier rafraîchissement réussi.
+pub fn spawn_refresh(cache: Arc<FeedCache>, every: Duration, on_first_load: impl FnOnce(&[Episode]) + Send + 'static) {
+    let mut first_load = FirstLoad(Some(Box::new(on_first_load)));
+    first_load.run_if_loaded(&cache);
+    tokio::spawn(async move {
+        // Pas de lecture immédiate : le chargement initial est déjà fait dans `main`.
+        loop {
+            let delay = if cache.episodes().is_empty() {
+                every.min(Duration::from_secs(EMPTY_CACHE_RETRY_SECS))
+            } else {
+                every
+            };
+            tokio::time::sleep(delay).await;
+            if let Err(e) = cache.refresh().await {
+                eprintln!("Rafraîchissement du flux impossible, ancienne copie conservée : {}", e);
+            }
+            first_load.run_if_loaded(&cache);
+        }
+    });
+}
+
+fn parse_episodes(xml: &str) -> Result<Vec<Episode>, FeedError> {
+    let mut reader = Reader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut episodes = Vec::new();
+    let mut in_item = false;
+    let mut title = String::new();
+    let mut date = String::new();
+    let mut description = String::new();
+    let mut image_url = String::new();
+    let mut guid = String::new();
+    let mut acast_id = String::new();
+    let mut season = String::new();
+    let mut number = String::new();
+    let mut episode_type = String::new();
+    let mut audio: Option<Enclosure> = None;
+    let mut duration = String::new();
+    let mut link = String::new();
+    let mut explicit = String::new();
+    let mut summary = String::new();
+    let mut chapters_url: Option<String> = None;
+    let mut transcripts: Vec<TranscriptLink> = Vec::new();
+    let mut persons: Vec<Person> = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) if e.name().as_ref() == b"item" => {
+                in_item = true;
+                title.clear(); date.clear(); description.clear(); image_url.clear();
+                guid.clear(); acast_id.clear();
+                season.clear(); number.clear(); episode_type.clear();
+                audio = None; duration.clear(); link.clear(); explicit.clear(); summary.clear();
+                chapters_url = None; transcripts.clear(); persons.clear();
+            }
+            Event::End(ref e) if e.name().as_ref() == b"item" => {
+                if in_item {
+                    let id = [&guid, &acast_id, &title]
+                        .into_iter()
+                        .find(|s| !s.is_empty())
+                        .cloned()
+                        .unwrap_or_default();
+                    let (title_season, title_number) = numbers_from_title(&title);
+                    episodes.push(Episode {
+                        guid: id,
+                        slug: String::new(),
+                        title: title.clone(),
+                        date: date.clone(),
+                        description: description.clone(),
+                        image_url: if image_url.is_empty() {
+                            "https://via.placeholder.com/350x200?text=No+Image".to_string()
+                        } else {
+                            image_url.clone()
+                        },
+                        season: season.parse().ok().or(title_season),
+                        number: number.parse().ok().or(title_number),
+                        episode_type: EpisodeType::parse(&episode_type, &title),
+                        audio: audio.take(),
+                        duration: parse_duration(&duration),
+                        link: Some(link.clone()).filter(|l| is_web_url(l)),
+                        explicit: matches!(explicit.to_lowercase().as_str(), "true" | "yes" | "explicit"),
+                        summary: Some(summary.clone()).filter(|s| !s.is_empty()),
+                        chapters_url: chapters_url.take(),
+                        transcripts: std::mem::take(&mut transcripts),
+                        persons: std::mem::take(&mut persons),
+                    });
+                }
+                in_item = false;
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"title" => {
+                title = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"guid" => {
+                guid = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"acast:episodeId" => {
+                acast_id = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"pubDate" => {
+                date = reader.read_text(e.name())?.trim().to_string();
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"description" => {
+                description = reader.read_text(e.name())?.trim().to_string();
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"itunes:season" => {
+                season = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"itunes:episode" => {
+                number = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"itunes:episodeType" => {
+                episode_type = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"itunes:duration" => {
+                duration = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"link" => {
+                link = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"itunes:explicit" => {
+                explicit = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"itunes:summary" => {
+                summary = text_content(&reader.read_text(e.name())?);
+            }
+            Event::Empty(ref e) | Event::Start(ref e) if in_item && e.name().as_ref() == b"enclosure" => {
+                if let Some(url) = attribute(e, b"url")? {
+                    audio = Some(Enclosure {
+                        url,
+                        mime_type: attribute(e, b"type")?,
+                        length: attribute(e, b"length")?.and_then(|l| l.parse().ok()),
+                    });
+                }
+            }
+            Event::Empty(ref e) | Event::Start(ref e) if in_item && e.name().as_ref() == b"podcast:chapters" => {
+                chapters_url = attribute(e, b"url")?;
+            }
+            Event::Empty(ref e) | Event::Start(ref e) if in_item && e.name().as_ref() == b"podcast:transcript" => {
+                if let Some(url) = attribute(e, b"url")? {
+                    transcripts.push(TranscriptLink {
+                        url,
+                        mime_type: attribute(e, b"type")?.unwrap_or_default().to_lowercase(),
+                        language: attribute(e, b"language")?,
+                    });
+                }
+            }
+            Event::Start(ref e) if in_item && e.name().as_ref() == b"podcast:person" => {
+                let role = attribute(e, b"role")?.map(|r| r.to_lowercase());
+                // Adresses reprises telles quelles dans la page : http(s) seulement
+                let img = attribute(e, b"img")?.filter(|url| is_web_url(url));
+                let href = attribute(e, b"href")?.filter(|url| is_web_url(url));
+                let name = text_content(&reader.read_text(e.name())?);
+                if !name.is_empty() {
+                    persons.push(Person { name, role, img, href });
+                }
+            }
+            Event::Empty(ref e) if in_item && e.name().as_ref() == b"itunes:image" => {
+                for attr in e.attributes() {
+                    let attr = attr.map_err(quick_xml::Error::from)?;
+                    if attr.key.as_ref() == b"href" {
+                        image_url = attr.unescape_value()?.trim().to_string();
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    if episodes.is_empty() {
+        return Err(FeedError::Empty);
+    }
+    assign_slugs(&mut episodes);
+    Ok(episodes)
+}
+
+/// Valeur non vide d'un attribut.
+fn attribute(e: &BytesStart, name: &[u8]) -> Result<Option<String>, FeedError> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(quick_xml::Error::from)?;
+        if attr.key.as_ref() == name {
+            let value = attr.unescape_value()?.trim().to_string();
+            return Ok(Some(value).filter(|v| !v.is_empty()));
+        }
+    }
+    Ok(None)
+}
+
+/// Texte d'un élément sans l'éventuelle enveloppe CDATA.
+fn text_content(raw: &str) -> String {
+    let text = raw.trim();
+    text.strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+        .unwrap_or(text)
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flux minimal : (guid, titre) du plus récent au plus ancien.
+    fn feed(items: &[(&str, &str)]) -> Vec<Episode> {
+        let items: String = items.iter()
+            .map(|(guid, title)| format!("<item><guid>{}</guid><title>{}</title></item>", guid, title))
+            .collect();
+        parse_episodes(&format!("<rss><channel>{}</channel></rss>", items)).unwrap()
+    }
+
+    #[test]
+    fn slug_has_guid_key_then_numbers_and_title() {
+        let episodes = feed(&[("guid-6", "PAP S2/E6 - Bienvenue chez McDonald's")]);
+        let slug = &episodes[0].slug;
+        assert_eq!(slug_key(slug), &guid_hash("guid-6")[..SLUG_KEY_LEN]);
+        assert!(slug.ends_with("-s2e6-bienvenue-chez-mcdonald-s"), "{}", slug);
+    }
+
+    #[test]
+    fn slug_survives_a_repeated_episode_number() {
+        let before = feed(&[("guid-6", "PAP S2/E6 - Bienvenue")]);
+        let after = feed(&[("guid-6-bis", "PAP S2/E6 - Bienvenue (rediffusion)"), ("guid-6", "PAP S2/E6 - Bienvenue")]);
+        assert_eq!(after[1].slug, before[0].slug);
+        assert_ne!(after[0].slug, after[1].slug);
+    }
+
+    #[test]
+    fn slug_ignores_position_in_feed() {
+        let before = feed(&[("a", "Premier")]);
+        let after = feed(&[("b", "Second"), ("a", "Premier")]);
+        assert_eq!(after[1].slug, before[0].slug);
+    }
+
+    #[test]
+    fn duplicate_guids_get_distinct_slugs_and_the_oldest_keeps_the_short_key() {
+        let episodes = feed(&[("same", "Nouveau"), ("same", "Ancien")]);
+        assert_eq!(slug_key(&episodes[1].slug).len(), SLUG_KEY_LEN);
+        assert_ne!(slug_key(&episodes[0].slug), slug_key(&episodes[1].slug));
+    }
+
+    #[test]
+    fn long_titles_are_cut_at_a_word_boundary() {
+        let title = "Un titre vraiment très long qui ne tiendrait pas dans une adresse raisonnable du tout";
+        let episodes = feed(&[("g", title)]);
+        let words = &episodes[0].slug[SLUG_KEY_LEN + 1..];
+        assert!(words.len() <= SLUG_TITLE_MAX, "{}", words);
+        assert!(slug_words(title).starts_with(&format!("{}-", words)));
+    }
+
+    #[test]
+    fn empty_title_gives_the_bare_key() {
+        let mut episodes = feed(&[("g", "Titre")]);
+        episodes[0].title = "!!!".to_string();
+        assign_slugs(&mut episodes);
+        assert_eq!(episodes[0].slug, &guid_hash("g")[..SLUG_KEY_LEN]);
+    }
+
+    #[test]
+    fn find_episode_accepts_outdated_addresses() {
+        let episodes = feed(&[("guid-7", "PAP S2/E7 - Gnocchis"), ("guid-6", "PAP S2/E6 - Bienvenue")]);
+        let ep6 = &episodes[1];
+        let find = |requested: &str| find_episode(&episodes, requested).map(|ep| ep.guid.as_str());
+        assert_eq!(find(&ep6.slug), Some("guid-6"));
+        // Titre changé depuis : même clé
+        assert_eq!(find(&format!("{}-ancien-titre", slug_key(&ep6.slug))), Some("guid-6"));
+        assert_eq!(find(slug_key(&ep6.slug)), Some("guid-6"));
+        assert_eq!(find("guid-6"), Some("guid-6"));
+        // Adresses des premières pages d'épisode
+        assert_eq!(find("s2e6-bienvenue"), Some("guid-6"));
+        assert_eq!(find("s2e7"), Some("guid-7"));
+        assert_eq!(find("s2e8-inconnu"), None);
+        assert_eq!(find("nimporte-quoi"), None);
+        assert_eq!(find(""), None);
+    }
+
+    #[test]
+    fn find_episode_prefers_the_matching_title_among_numbered_duplicates() {
+        let episodes = feed(&[("bis", "PAP S2/E6 - Rediffusion"), ("orig", "PAP S2/E6 - Bienvenue")]);
+        assert_eq!(find_episode(&episodes, "s2e6-bienvenue").map(|ep| ep.guid.as_str()), Some("orig"));
+        assert_eq!(find_episode(&episodes, "s2e6-rediffusion").map(|ep| ep.guid.as_str()), Some("bis"));
+    }
+
+    #[test]
+    fn numbers_from_usual_titles() {
+        assert_eq!(numbers_from_title("PAP S2/E6 - Bienvenue"), (Some(2), Some(6)));
+        assert_eq!(numbers_from_title("pap s2e6"), (Some(2), Some(6)));
+        assert_eq!(numbers_from_title("PAP S02 - E06 : Titre"), (Some(2), Some(6)));
+        assert_eq!(numbers_from_title("Saison 3 E4"), (Some(3), Some(4)));
+        assert_eq!(numbers_from_title("Épisode spécial S1.E12"), (Some(1), Some(12)));
+        assert_eq!(numbers_from_title("PAP S3 - Bande-annonce"), (Some(3), None));
+    }
+
+    #[test]
+    fn numbers_from_malformed_titles() {
+        assert_eq!(numbers_from_title(""), (None, None));
+        assert_eq!(numbers_from_title("S"), (None, None));
+        assert_eq!(numbers_from_title("Les 3 mousquetaires"), (None, None));
+        assert_eq!(numbers_from_title("Spécial 2024"), (None, None));
+        assert_eq!(numbers_from_title("PAP S/E6"), (None, None));
+        assert_eq!(numbers_from_title("PAP S99999999999/E1"), (None, None));
+        assert_eq!(numbers_from_title("PAP S2/E"), (Some(2), None));
+        assert_eq!(numbers_from_title("PAP S2/Ex"), (Some(2), None));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("2712"), Some(2712));
+        assert_eq!(parse_duration("45:12"), Some(45 * 60 + 12));
+        assert_eq!(parse_duration(" 1:02:03 "), Some(3723));
+        assert_eq!(parse_duration("00:00"), Some(0));
+        assert_eq!(parse_duration("75:00"), Some(75 * 60));
+        assert_eq!(parse_duration("1:59:59"), Some(7199));
+    }
+
+    #[test]
+    fn malformed_durations() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("45 min"), None);
+        assert_eq!(parse_duration("12.5"), None);
+        assert_eq!(parse_duration("-5"), None);
+        assert_eq!(parse_duration("1::03"), None);
+        assert_eq!(parse_duration("1:02:"), None);
+        assert_eq!(parse_duration("99999999999"), None);
+        assert_eq!(parse_duration("9999999:00:00"), None);
+        assert_eq!(parse_duration("1:99"), None);
+        assert_eq!(parse_duration("1:60"), None);
+        assert_eq!(parse_duration("1:60:00"), None);
+        assert_eq!(parse_duration("1:2:3:4"), None);
+        assert_eq!(parse_duration("0:0:0:0:0"), None);
+    }
+
+    #[tokio::test]
+    async fn first_load_action_waits_for_a_successful_refresh() {
+        use std::sync::Mutex;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use axum::http::StatusCode as HttpStatus;
+
+        // Flux en panne jusqu'à ce que `up` passe à vrai
+        let up = Arc::new(AtomicBool::new(false));
+        let app = axum::Router::new().route("/feed.xml", axum::routing::get({
+            let up = up.clone();
+            move || async move {
+                if !up.load(Ordering::SeqCst) {
+                    return Err(HttpStatus::SERVICE_UNAVAILABLE);
+                }
+                Ok("<rss><channel><item><guid>guid-1</guid><title>PAP S1/E1</title></item></channel></rss>")
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let cache = FeedCache::new(url);
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let mut first_load = FirstLoad(Some(Box::new({
+            let runs = runs.clone();
+            move |episodes: &[Episode]| runs.lock().unwrap().push(episodes.len())
+        })));
+
+        assert!(cache.refresh().await.is_err());
+        first_load.run_if_loaded(&cache);
+        assert!(runs.lock().unwrap().is_empty());
+
+        up.store(true, Ordering::SeqCst);
+        cache.refresh().await.unwrap();
+        first_load.run_if_loaded(&cache);
+        cache.refresh().await.unwrap();
+        first_load.run_if_loaded(&cache);
+        assert_eq!(*runs.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn episode_link_keeps_only_web_urls() {
+        let item = |link: &str| format!("<item><guid>{}</guid><title>T</title><link>{}</link></item>", link, link);
+        let xml = format!("<rss><channel>{}{}{}</channel></rss>",
+            item("https://exemple.fr/ep"), item("javascript:alert(1)"), item(""));
+        let links: Vec<Option<String>> = parse_episodes(&xml).unwrap().into_iter().map(|ep| ep.link).collect();
+        assert_eq!(links, vec![Some("https://exemple.fr/ep".to_string()), None, None]);
+    }
+
+    #[test]
+    fn person_links_keep_only_web_urls() {
+        let xml = r#"<rss><channel><item><guid>g</guid><title>T</title>
+            <podcast:person href="https://exemple.fr/jean" img="https://exemple.fr/jean.jpg">Jean</podcast:person>
+            <podcast:person href="javascript:alert(1)" img="data:image/svg+xml,x">Marie</podcast:person>
+            <podcast:person href=" JavaScript:alert(1)">Paul</podcast:person>
+        </item></channel></rss>"#;
+        let persons = &parse_episodes(xml).unwrap()[0].persons;
+        let links: Vec<(&str, Option<&str>, Option<&str>)> =
+            persons.iter().map(|p| (p.name.as_str(), p.href.as_deref(), p.img.as_deref())).collect();
+        assert_eq!(links, vec![
+            ("Jean", Some("https://exemple.fr/jean"), Some("https://exemple.fr/jean.jpg")),
+            ("Marie", None, None),
+            ("Paul", None, None),
+        ]);
+    }
+}
@@ -0,0 +1,289 @@
+//! Filtres et tris des recommandations, par paramètres d'URL (donc partageables) :
+//! `?type=livre&chroniqueur=jean&saison=2&tri=episode`, sur la page d'une émission et sur
+//! `/recommandations`, qui liste les recommandations de toutes les émissions.
+
+use std::str::FromStr;
+use axum::extract::{Query, State};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Deserializer};
+use crate::categories;
+use crate::episodes;
+use crate::feed::Episode;
+use crate::store::{Recommendation, Status};
+use crate::{AppState, ShowState, show_not_found, store_error};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sort {
+    /// Plus récent d'abord (ordre du flux pour les épisodes, date d'ajout pour les recommandations).
+    #[default]
+    Date,
+    /// Par saison puis numéro d'épisode.
+    Episode,
+}
+
+impl FromStr for Sort {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Sort, Self::Err> {
+        match s {
+            "date" => Ok(Sort::Date),
+            "episode" => Ok(Sort::Episode),
+            _ => Err("tri inconnu (date ou episode)"),
+        }
+    }
+}
+
+/// Un champ laissé vide dans le formulaire (`?saison=`) ou illisible (`?saison=abc`,
+/// `?tri=titre`) vaut « pas de filtre » : une adresse partagée abîmée affiche la page
+/// non filtrée plutôt qu'une erreur.
+fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.as_deref().map(str::trim).filter(|value| !value.is_empty()).and_then(|value| value.parse().ok()))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RecoFilter {
+    /// Identifiant de catégorie.
+    #[serde(rename = "type", default, deserialize_with = "empty_as_none")]
+    pub type_media: Option<String>,
+    /// Identifiant de chroniqueur.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub chroniqueur: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub saison: Option<u32>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub tri: Option<Sort>,
+    /// Émission (page `/recommandations` seulement).
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub show: Option<String>,
+}
+
+impl RecoFilter {
+    /// Vrai si un filtre porte sur les recommandations elles-mêmes (les épisodes sans
+    /// recommandation correspondante sont alors masqués).
+    pub fn filters_recommendations(&self) -> bool {
+        self.type_media.is_some() || self.chroniqueur.is_some()
+    }
+
+    pub fn matches(&self, rec: &Recommendation) -> bool {
+        self.type_media.as_ref().is_none_or(|t| &rec.type_media == t)
+            && self.chroniqueur.as_ref().is_none_or(|c| rec.chroniqueurs.contains(c))
+    }
+
+    pub fn matches_episode(&self, episode: &Episode) -> bool {
+        self.saison.is_none_or(|saison| episode.season == Some(saison))
+    }
+
+    pub fn sort(&self) -> Sort {
+        self.tri.unwrap_or_default()
+    }
+
+    /// Formulaire de filtres envoyé en GET vers `action` ; `seasons` alimente le choix de saison.
+    pub fn form(&self, state: &AppState, action: &str, seasons: &[u32], show_choice: bool) -> String {
+        let option = |value: &str, label: &str, selected: bool| format!(
+            "<option value='{}'{}>{}</option>",
+            htmlescape::encode_attribute(value),
+            if selected { " selected" } else { "" },
+            htmlescape::encode_minimal(label)
+        );
+        let select = |name: &str, label: &str, options: String| format!(
+            "<label style='display:flex;flex-direction:column;font-size:0.85em;color:#555;'>{}\
+                <select name='{}' onchange='this.form.submit()' style='padding:0.3em;border-radius:8px;border:1px solid #ccc;'>{}</select>\
+            </label>",
+            label, name, options
+        );
+        let mut fields = String::new();
+        if show_choice && state.shows.len() > 1 {
+            let mut options = option("", "Toutes", self.show.is_none());
+            for show in state.shows.iter() {
+                options.push_str(&option(&show.show.slug, &show.show.name, self.show.as_deref() == Some(&show.show.slug)));
+            }
+            fields.push_str(&select("show", "Émission", options));
+        }
+        let mut options = option("", "Tous", self.type_media.is_none());
+        for category in state.categories.all() {
+            let label = format!("{} {}", category.icon, category.label);
+            options.push_str(&option(&category.id, &label, self.type_media.as_deref() == Some(&category.id)));
+        }
+        fields.push_str(&select("type", "Type", options));
+        let mut options = option("", "Tous", self.chroniqueur.is_none());
+        for host in state.hosts.all() {
+            options.push_str(&option(&host.id, &host.name, self.chroniqueur.as_deref() == Some(&host.id)));
+        }
+        fields.push_str(&select("chroniqueur", "Chroniqueur", options));
+        if !seasons.is_empty() {
+            let mut options = option("", "Toutes", self.saison.is_none());
+            for season in seasons {
+                options.push_str(&option(&season.to_string(), &format!("Saison {}", season), self.saison == Some(*season)));
+            }
+            fields.push_str(&select("saison", "Saison", options));
+        }
+        let options = option("date", "Plus récents", self.sort() == Sort::Date)
+            + &option("episode", "Par épisode", self.sort() == Sort::Episode);
+        fields.push_str(&select("tri", "Tri", options));
+        format!(
+            "<form class='reco-filters' method='get' action='{action}' style='display:flex;flex-wrap:wrap;gap:0.8em;align-items:flex-end;background:rgba(255,255,255,0.92);border-radius:14px;padding:0.7em 1.2em;margin:0 auto 1.5em auto;max-width:700px;'>\
+                {fields}\
+                <noscript><button type='submit'>Filtrer</button></noscript>\
+                <a href='{action}' style='font-size:0.85em;'>Tout afficher</a>\
+            </form>",
+            action = htmlescape::encode_attribute(action),
+            fields = fields,
+        )
+    }
+}
+
+/// Saisons présentes dans des épisodes, par ordre croissant.
+pub fn seasons<'a>(episodes: impl IntoIterator<Item = &'a Episode>) -> Vec<u32> {
+    let mut seasons: Vec<u32> = episodes.into_iter().filter_map(|episode| episode.season).collect();
+    seasons.sort_unstable();
+    seasons.dedup();
+    seasons
+}
+
+/// Clé de tri « par épisode » : saison puis numéro, les épisodes non numérotés à la fin.
+pub fn episode_order(episode: &Episode) -> (u32, u32) {
+    match episode.season {
+        Some(season) => (season, episode.number.unwrap_or(u32::MAX)),
+        None => (u32::MAX, u32::MAX),
+    }
+}
+
+// Page `/recommandations`
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/recommandations", get(recommendations_page))
+}
+
+async fn recommendations_page(State(state): State<AppState>, Query(filter): Query<RecoFilter>) -> Response {
+    let shows: Vec<&ShowState> = match &filter.show {
+        Some(slug) => match state.show(Some(slug)) {
+            Some(show) => vec![show],
+            None => return show_not_found(),
+        },
+        None => state.shows.iter().collect(),
+    };
+    // (émission, épisode, position de l'épisode dans le flux, recommandation)
+    let mut found: Vec<(&ShowState, Episode, usize, Recommendation)> = Vec::new();
+    let mut all_episodes = Vec::new();
+    for show in &shows {
+        let episodes = show.feed.episodes();
+        let mut map = match show.store.all() {
+            Ok(map) => map,
+            Err(e) => return store_error(e),
+        };
+        for (position, episode) in episodes.iter().enumerate() {
+            all_episodes.push(episode.clone());
+            if !filter.matches_episode(episode) {
+                continue;
+            }
+            let recs = map.remove(&episode.guid).unwrap_or_default();
+            found.extend(recs.into_iter()
+                .filter(|rec| rec.status == Status::Approved && filter.matches(rec))
+                .map(|rec| (*show, episode.clone(), position, rec)));
+        }
+    }
+    match filter.sort() {
+        Sort::Date => found.sort_by_key(|(_, _, _, rec)| std::cmp::Reverse(rec.created_at)),
+        // Le tri est stable : à épisode égal, l'ordre d'ajout est conservé
+        Sort::Episode => found.sort_by_key(|(show, episode, position, _)| {
+            let show_index = state.shows.iter().position(|s| std::ptr::eq(s, *show));
+            (show_index, episode_order(episode), *position)
+        }),
+    }
+    let items: String = found.iter().map(|(show, episode, _, rec)| {
+        let titre = match &rec.lien {
+            Some(lien) => format!(
+                "<a href='{}' target='_blank' rel='noopener'>{}</a>",
+                htmlescape::encode_attribute(lien), htmlescape::encode_minimal(&rec.titre)
+            ),
+            None => htmlescape::encode_minimal(&rec.titre),
+        };
+        let chroniqueurs: Vec<String> = rec.chroniqueurs.iter().map(|id| format!(
+            "<a href='/chroniqueurs/{}' style='color:inherit;'>{}</a>",
+            htmlescape::encode_attribute(id),
+            htmlescape::encode_minimal(&state.hosts.display_name(id))
+        )).collect();
+        let show_name = if state.shows.len() > 1 {
+            format!("{} · ", htmlescape::encode_minimal(&show.show.name))
+        } else {
+            String::new()
+        };
+        format!(
+            "<li style='background:#fff;border-radius:14px;box-shadow:0 2px 8px #0001;padding:0.9em 1.2em;margin-bottom:0.8em;list-style:none;'>\
+                <div style='display:flex;justify-content:space-between;gap:1em;'><b>{titre}</b>{badge}</div>\
+                <div style='color:#9147ff;font-style:italic;margin-top:0.3em;'>{chroniqueurs}</div>\
+                <div style='margin:0.3em 0;'>{description}</div>\
+                <div style='color:#888;font-size:0.9em;'>{show_name}<a href='{path}'>{episode}</a></div>\
+            </li>",
+            titre = titre,
+            badge = categories::badge(&state.categories.display(&rec.type_media)),
+            chroniqueurs = crate::chroniqueurs_text(&chroniqueurs),
+            description = htmlescape::encode_minimal(&rec.description),
+            show_name = show_name,
+            path = htmlescape::encode_attribute(&episodes::episode_path(show, episode)),
+            episode = htmlescape::encode_minimal(&episode.title),
+        )
+    }).collect();
+    let list = if items.is_empty() {
+        "<p>Aucune recommandation ne correspond à ces filtres.</p>".to_string()
+    } else {
+        format!("<p style='color:#555;'>{} recommandation(s)</p><ul style='padding:0;'>{}</ul>", found.len(), items)
+    };
+    Html(format!(
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Toutes les recommandations</title>
+        </head>
+        <body style="font-family:sans-serif;margin:0;background:#f3f6fa;">
+            <main style="max-width:760px;margin:0 auto;padding:2em 1em;">
+                <a href="/">&larr; Retour au site</a>
+                <h1>Toutes les recommandations</h1>
+                {form}
+                {list}
+            </main>
+        </body>
+        </html>"#,
+        form = filter.form(&state, "/recommandations", &seasons(&all_episodes), true),
+        list = list,
+    )).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str) -> RecoFilter {
+        let uri = format!("/recommandations?{}", query).parse().unwrap();
+        Query::<RecoFilter>::try_from_uri(&uri).unwrap().0
+    }
+
+    #[test]
+    fn valid_values_are_kept() {
+        let filter = parse("type=livre&chroniqueur=jean&saison=2&tri=episode&show=pap");
+        assert_eq!(filter.type_media.as_deref(), Some("livre"));
+        assert_eq!(filter.chroniqueur.as_deref(), Some("jean"));
+        assert_eq!(filter.saison, Some(2));
+        assert_eq!(filter.tri, Some(Sort::Episode));
+        assert_eq!(filter.show.as_deref(), Some("pap"));
+    }
+
+    #[test]
+    fn empty_and_unparseable_values_are_ignored() {
+        let filter = parse("type=&chroniqueur=%20&saison=abc&tri=titre");
+        assert!(filter.type_media.is_none() && filter.chroniqueur.is_none());
+        assert_eq!(filter.saison, None);
+        assert_eq!(filter.tri, None);
+        assert_eq!(parse("saison=-1&tri=").saison, None);
+        assert_eq!(parse("saison=%202%20").saison, Some(2));
+    }
+}
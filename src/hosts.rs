@@ -0,0 +1,439 @@
+//! Registre des chroniqueurs, partagé par toutes les émissions.
+//!
+//! Les recommandations désignent leurs chroniqueurs par identifiant (`Host::id`) ; le registre
+//! donne le nom affiché, l'avatar, la bio et les liens. Il est gardé dans un fichier JSON
+//! (`hosts_file`) et modifiable par les administrateurs sur `/admin/chroniqueurs`.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use axum::extract::{Form, Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::http::header::LOCATION;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use crate::auth::Admin;
+use crate::show::is_valid_slug;
+use crate::store::{self, RecommendationsStore, StoreError};
+use crate::{AppState, store_error};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Host {
+    /// Identifiant stable, utilisé dans les recommandations et les URL.
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    /// Réseaux sociaux, site personnel...
+    #[serde(default)]
+    pub links: Vec<String>,
+    /// Autres graphies du nom rencontrées dans les anciennes recommandations (« Jean D. »...).
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl Host {
+    /// Vrai si `name` désigne ce chroniqueur (identifiant, nom ou alias, à la casse et aux accents près).
+    fn answers_to(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        normalize_name(&self.id) == name
+            || normalize_name(&self.name) == name
+            || self.aliases.iter().any(|alias| normalize_name(alias) == name)
+    }
+}
+
+/// Minuscules sans accents, espaces réduits : « Jéan  Dupont » et « jean dupont » se confondent.
+pub fn normalize_name(name: &str) -> String {
+    fold_accents(&name.to_lowercase()).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Retire les accents des lettres latines courantes (le texte doit déjà être en minuscules).
+pub fn fold_accents(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'à' | 'â' | 'ä' | 'á' | 'ã' | 'å' => folded.push('a'),
+            'ç' => folded.push('c'),
+            'é' | 'è' | 'ê' | 'ë' => folded.push('e'),
+            'î' | 'ï' | 'í' | 'ì' => folded.push('i'),
+            'ô' | 'ö' | 'ó' | 'ò' | 'õ' => folded.push('o'),
+            'ù' | 'û' | 'ü' | 'ú' => folded.push('u'),
+            'ÿ' | 'ý' => folded.push('y'),
+            'ñ' => folded.push('n'),
+            'œ' => folded.push_str("oe"),
+            'æ' => folded.push_str("ae"),
+            _ => folded.push(c),
+        }
+    }
+    folded
+}
+
+/// Identifiant dérivé d'un nom : « Jean Dupont » → `jean-dupont`.
+fn slugify(name: &str) -> String {
+    let folded = normalize_name(name);
+    let slug: String = folded
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() { "chroniqueur".to_string() } else { slug }
+}
+
+pub struct HostRegistry {
+    path: PathBuf,
+    hosts: Mutex<Vec<Host>>,
+    /// Incrémenté à chaque modification (les noms sont indexés par la recherche).
+    revision: AtomicU64,
+}
+
+impl HostRegistry {
+    /// Ouvre le registre, vide si le fichier n'existe pas encore.
+    pub fn open(path: &Path) -> Result<HostRegistry, StoreError> {
+        let hosts = match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| StoreError::Corrupt(path.to_path_buf(), e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(HostRegistry { path: path.to_path_buf(), hosts: Mutex::new(hosts), revision: AtomicU64::new(0) })
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    /// Tous les chroniqueurs, par ordre alphabétique.
+    pub fn all(&self) -> Vec<Host> {
+        let mut hosts = self.hosts.lock().unwrap().clone();
+        hosts.sort_by_key(|h| normalize_name(&h.name));
+        hosts
+    }
+
+    pub fn get(&self, id: &str) -> Option<Host> {
+        self.hosts.lock().unwrap().iter().find(|h| h.id == id).cloned()
+    }
+
+    /// Chroniqueur désigné par un nom libre (identifiant, nom ou alias).
+    pub fn find(&self, name: &str) -> Option<Host> {
+        self.hosts.lock().unwrap().iter().find(|h| h.answers_to(name)).cloned()
+    }
+
+    /// Nom affiché d'un identifiant ; l'identifiant lui-même s'il n'est pas au registre.
+    pub fn display_name(&self, id: &str) -> String {
+        self.get(id).map_or_else(|| id.to_string(), |h| h.name)
+    }
+
+    /// Refuse une saisie qui désigne un chroniqueur absent du registre.
+    pub fn check_known(&self, ids: &[String]) -> Result<(), String> {
+        let hosts = self.hosts.lock().unwrap();
+        match ids.iter().find(|id| !hosts.iter().any(|h| &h.id == *id)) {
+            Some(id) => Err(format!("Chroniqueur inconnu : {}", id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Ajoute ou remplace (même identifiant) un chroniqueur.
+    pub fn save(&self, host: Host) -> Result<(), StoreError> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let mut updated = hosts.clone();
+        match updated.iter_mut().find(|h| h.id == host.id) {
+            Some(existing) => *existing = host,
+            None => updated.push(host),
+        }
+        store::write_json_file(&self.path, &updated)?;
+        *hosts = updated;
+        self.revision.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Identifiant du chroniqueur `name`, créé dans le registre s'il n'y figure pas.
+    ///
+    /// Un identifiant existant est gardé tel quel : sinon, un autre chroniqueur dont le nom ou un
+    /// alias s'écrit pareil pourrait le récupérer à chaque démarrage.
+    fn resolve_or_create(&self, name: &str) -> Result<(String, bool), StoreError> {
+        if let Some(host) = self.get(name) {
+            return Ok((host.id, false));
+        }
+        if let Some(host) = self.find(name) {
+            return Ok((host.id, false));
+        }
+        let base = slugify(name);
+        let mut id = base.clone();
+        let mut n = 2;
+        while self.get(&id).is_some() {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+        self.save(Host {
+            id: id.clone(),
+            name: name.trim().to_string(),
+            avatar_url: None,
+            bio: None,
+            links: Vec::new(),
+            aliases: Vec::new(),
+        })?;
+        Ok((id, true))
+    }
+}
+
+/// Remplace les noms libres des recommandations par des identifiants du registre.
+///
+/// Chaque nom est rapproché d'un chroniqueur existant (identifiant, nom ou alias, sans tenir
+/// compte de la casse ni des accents) ; les noms inconnus créent une fiche, à compléter ou à
+/// fusionner ensuite en ajoutant des alias. Renvoie les noms des fiches créées. Les valeurs qui
+/// sont déjà des identifiants du registre ne sont pas touchées : la migration peut tourner à
+/// chaque démarrage sans rien changer une fois faite.
+pub fn migrate_names_to_ids(store: &RecommendationsStore, hosts: &HostRegistry) -> Result<Vec<String>, StoreError> {
+    let mut map = store.all()?;
+    let mut created = Vec::new();
+    let mut changed = false;
+    for rec in map.values_mut().flatten() {
+        let mut ids: Vec<String> = Vec::new();
+        for name in &rec.chroniqueurs {
+            let (id, new) = hosts.resolve_or_create(name)?;
+            if new {
+                created.push(name.trim().to_string());
+            }
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        if ids != rec.chroniqueurs {
+            rec.chroniqueurs = ids;
+            changed = true;
+        }
+    }
+    if changed {
+        store.replace_all(&map)?;
+    }
+    Ok(created)
+}
+
+// Pages d'administration du registre
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/chroniqueurs", get(hosts_page).post(save_host))
+        .route("/admin/chroniqueurs/{id}", get(edit_host_page))
+}
+
+const ADMIN_PAGE: &str = "/admin/chroniqueurs";
+
+/// Page d'administration (registre des chroniqueurs, catégories) ; les formulaires ont la classe `admin-form`.
+pub fn admin_page(title: &str, body: String) -> Response {
+    Html(format!(
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>{title}</title>
+            <style>
+                .admin-form label {{ display:block; margin-top:0.9em; font-weight:500; }}
+                .admin-form input, .admin-form textarea {{ width:100%; padding:0.5em; border-radius:8px; border:1px solid #ccc; box-sizing:border-box; }}
+                .admin-form button {{ margin-top:1.2em; background:linear-gradient(90deg,#ffb347,#ffcc33); font-weight:bold; border:none; border-radius:2em; padding:0.6em 2em; cursor:pointer; }}
+            </style>
+        </head>
+        <body style="font-family:sans-serif;margin:0;background:#f3f6fa;">
+            <main style="max-width:760px;margin:0 auto;padding:2em 1em;">
+                <a href="/">&larr; Retour au site</a>
+                <h1>{title}</h1>
+                {body}
+            </main>
+        </body>
+        </html>"#,
+        title = htmlescape::encode_minimal(title),
+        body = body,
+    )).into_response()
+}
+
+/// Formulaire de création (`host` vide) ou de modification d'une fiche.
+fn host_form(host: Option<&Host>, csrf_token: &str) -> String {
+    let field = |value: Option<&str>| htmlescape::encode_attribute(value.unwrap_or(""));
+    let id_input = match host {
+        Some(host) => format!(
+            "<input type='hidden' name='id' value='{id}'><input type='hidden' name='existing' value='true'>\
+             <p>Identifiant : <code>{id}</code></p>",
+            id = htmlescape::encode_attribute(&host.id)
+        ),
+        None => "<label for='id'>Identifiant (minuscules, chiffres, tirets ; utilisé dans les URL)</label>\
+                 <input id='id' name='id' required pattern='[a-z0-9-]+'>".to_string(),
+    };
+    format!(
+        "<form class='admin-form' method='post' action='{action}' style='background:#fff;border-radius:14px;box-shadow:0 2px 8px #0001;padding:1.2em 1.5em;'>\
+            <input type='hidden' name='csrf_token' value='{csrf}'>\
+            {id_input}\
+            <label for='name'>Nom affiché</label>\
+            <input id='name' name='name' required value='{name}'>\
+            <label for='avatar_url'>Avatar (URL)</label>\
+            <input id='avatar_url' name='avatar_url' value='{avatar}'>\
+            <label for='bio'>Bio</label>\
+            <textarea id='bio' name='bio' rows='3'>{bio}</textarea>\
+            <label for='links'>Liens (un par ligne)</label>\
+            <textarea id='links' name='links' rows='3'>{links}</textarea>\
+            <label for='aliases'>Autres graphies du nom (séparées par des virgules)</label>\
+            <input id='aliases' name='aliases' value='{aliases}'>\
+            <button type='submit'>Enregistrer</button>\
+        </form>",
+        action = ADMIN_PAGE,
+        csrf = csrf_token,
+        id_input = id_input,
+        name = field(host.map(|h| h.name.as_str())),
+        avatar = field(host.and_then(|h| h.avatar_url.as_deref())),
+        bio = htmlescape::encode_minimal(host.and_then(|h| h.bio.as_deref()).unwrap_or("")),
+        links = htmlescape::encode_minimal(&host.map(|h| h.links.join("\n")).unwrap_or_default()),
+        aliases = field(host.map(|h| h.aliases.join(", ")).as_deref()),
+    )
+}
+
+async fn hosts_page(State(state): State<AppState>, Admin(user): Admin) -> Response {
+    let rows: String = state.hosts.all().iter().map(|host| format!(
+        "<li style='margin-bottom:0.4em;'><a href='{page}/{id}'>{name}</a> <code style='color:#888;'>{id}</code></li>",
+        page = ADMIN_PAGE,
+        id = htmlescape::encode_attribute(&host.id),
+        name = htmlescape::encode_minimal(&host.name),
+    )).collect();
+    let list = if rows.is_empty() { "<p>Aucun chroniqueur enregistré.</p>".to_string() } else { format!("<ul>{}</ul>", rows) };
+    admin_page("Chroniqueurs", format!(
+        "{}<h2>Nouveau chroniqueur</h2>{}",
+        list,
+        host_form(None, &user.csrf_token)
+    ))
+}
+
+async fn edit_host_page(State(state): State<AppState>, Admin(user): Admin, UrlPath(id): UrlPath<String>) -> Response {
+    match state.hosts.get(&id) {
+        Some(host) => admin_page(&host.name, host_form(Some(&host), &user.csrf_token)),
+        None => (StatusCode::NOT_FOUND, "Chroniqueur inconnu").into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct HostForm {
+    csrf_token: String,
+    id: String,
+    /// Présent quand le formulaire modifie une fiche existante.
+    #[serde(default)]
+    existing: bool,
+    name: String,
+    avatar_url: String,
+    bio: String,
+    links: String,
+    aliases: String,
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    Some(value.trim()).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+async fn save_host(State(state): State<AppState>, Admin(user): Admin, Form(form): Form<HostForm>) -> Response {
+    if let Err(e) = user.check_csrf(&form.csrf_token) {
+        return e.into_response();
+    }
+    let id = form.id.trim().to_string();
+    if !is_valid_slug(&id) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Identifiant invalide (minuscules, chiffres et tirets uniquement)").into_response();
+    }
+    if state.hosts.get(&id).is_some() != form.existing {
+        let message = if form.existing { "Chroniqueur inconnu" } else { "Cet identifiant est déjà utilisé" };
+        return (StatusCode::CONFLICT, message).into_response();
+    }
+    let Some(name) = non_empty(&form.name) else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Le nom est obligatoire").into_response();
+    };
+    let host = Host {
+        id,
+        name,
+        avatar_url: non_empty(&form.avatar_url),
+        bio: non_empty(&form.bio),
+        links: form.links.lines().filter_map(non_empty).collect(),
+        aliases: form.aliases.split(',').filter_map(non_empty).collect(),
+    };
+    match state.hosts.save(host) {
+        Ok(()) => (StatusCode::SEE_OTHER, [(LOCATION, ADMIN_PAGE)]).into_response(),
+        Err(e) => store_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::store::{RecommendationInput, SqliteStore, Status, open_database};
+
+    fn host(id: &str, name: &str, aliases: &[&str]) -> Host {
+        Host {
+            id: id.to_string(),
+            name: name.to_string(),
+            avatar_url: None,
+            bio: None,
+            links: Vec::new(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// Registre dans un fichier temporaire, avec ces fiches dans cet ordre.
+    fn registry(hosts: &[Host]) -> HostRegistry {
+        let path = std::env::temp_dir().join(format!("pap-hosts-{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, serde_json::to_string(hosts).unwrap()).unwrap();
+        HostRegistry::open(&path).unwrap()
+    }
+
+    fn store_with(chroniqueurs: &[&[&str]]) -> RecommendationsStore {
+        let store: RecommendationsStore = Arc::new(SqliteStore::new(open_database(Path::new(":memory:")).unwrap(), "pap"));
+        for names in chroniqueurs {
+            let rec = RecommendationInput {
+                chroniqueurs: names.iter().map(|n| n.to_string()).collect(),
+                titre: "Titre".to_string(),
+                lien: None,
+                type_media: "livre".to_string(),
+                description: "Description".to_string(),
+                timestamp: None,
+            }.into_recommendation(Status::Approved);
+            store.insert("guid-1", &rec).unwrap();
+        }
+        store
+    }
+
+    fn chroniqueurs(store: &RecommendationsStore) -> Vec<Vec<String>> {
+        store.list("guid-1").unwrap().into_iter().map(|rec| rec.chroniqueurs).collect()
+    }
+
+    #[test]
+    fn existing_ids_are_kept_even_when_another_host_answers_to_them() {
+        // « marc » est l'identifiant de Marc, mais aussi un alias de Jean, placé avant lui
+        let hosts = registry(&[host("jean", "Jean Dupont", &["marc"]), host("marc", "Marc Martin", &[])]);
+        let store = store_with(&[&["marc"], &["jean", "marc"]]);
+
+        migrate_names_to_ids(&store, &hosts).unwrap();
+
+        assert_eq!(chroniqueurs(&store), vec![vec!["marc"], vec!["jean", "marc"]]);
+    }
+
+    #[test]
+    fn free_names_are_resolved_once_and_the_migration_is_idempotent() {
+        let hosts = registry(&[host("jean", "Jean Dupont", &["Jean D."])]);
+        let store = store_with(&[&["Jéan  dupont"], &["Jean D.", "Paul Nouveau"], &["paul nouveau"]]);
+
+        let created = migrate_names_to_ids(&store, &hosts).unwrap();
+        let first = chroniqueurs(&store);
+        let created_again = migrate_names_to_ids(&store, &hosts).unwrap();
+
+        assert_eq!(created, vec!["Paul Nouveau"]);
+        assert_eq!(first, vec![vec!["jean"], vec!["jean", "paul-nouveau"], vec!["paul-nouveau"]]);
+        assert!(created_again.is_empty());
+        assert_eq!(chroniqueurs(&store), first);
+        assert_eq!(hosts.all().len(), 2);
+    }
+
+    #[test]
+    fn slugify_falls_back_for_names_without_letters() {
+        assert_eq!(slugify("Élodie  Ça-Va"), "elodie-ca-va");
+        assert_eq!(slugify("???"), "chroniqueur");
+    }
+}
@@ -1,258 +1,609 @@
-use std::fs;
-use axum::{Router, response::Html, routing::{get, post}, extract::{Form, State}};
-use std::net::SocketAddr;
-use tokio;
+mod api;
+mod auth;
+mod categories;
+mod config;
+mod episodes;
+mod feed;
+mod filters;
+mod hosts;
+mod moderation;
+mod podcast;
+mod positions;
+mod profiles;
+mod search;
+mod show;
+mod store;
+mod throttle;
+
+use axum::{Router, response::Html, routing::{get, post}, extract::{ConnectInfo, Form, Path as UrlPath, Query, State}};
+use axum_extra::extract::Form as MultiForm;
 use tokio::net::TcpListener;
-use reqwest;
-use quick_xml::events::Event;
-use quick_xml::Reader;
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use serde::{Serialize, Deserialize};
-use serde_json;
-use axum::response::IntoResponse;
-use htmlescape;
-
-#[derive(Debug)]
-struct Episode {
-    title: String,
-    date: String,
-    description: String,
-    image_url: String,
+use std::net::SocketAddr;
+use std::sync::Arc;
+use serde::Deserialize;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use auth::{Auth, CurrentUser};
+use categories::CategoryRegistry;
+use config::{Command, Config, StorageKind};
+use feed::FeedCache;
+use filters::{RecoFilter, Sort};
+use hosts::HostRegistry;
+use podcast::ExtrasCache;
+use positions::PositionStore;
+use search::SearchIndex;
+use show::Show;
+use throttle::Throttle;
+use store::{JsonStore, RecommendationInput, RecommendationsStore, SqliteStore, Status, StoreError};
+
+/// Délai suggéré avant de recharger la page quand le flux est indisponible.
+const FEED_RETRY_AFTER_SECS: u64 = 30;
+
+/// Une émission servie, avec son flux en cache et ses recommandations.
+struct ShowState {
+    show: Show,
+    feed: Arc<FeedCache>,
+    store: RecommendationsStore,
+    /// Vrai si d'autres émissions sont servies (affiche le lien vers l'index).
+    has_siblings: bool,
+}
+
+impl ShowState {
+    /// Adresse de la page de l'émission.
+    fn page_path(&self) -> String {
+        if self.has_siblings { format!("/shows/{}", self.show.slug) } else { "/".to_string() }
+    }
+
+    /// Refuse un minutage au-delà de la durée annoncée par le flux (`itunes:duration`).
+    fn check_timestamp(&self, episode_id: &str, timestamp: Option<u32>) -> Result<(), String> {
+        let Some(timestamp) = timestamp else {
+            return Ok(());
+        };
+        let episodes = self.feed.episodes();
+        let duration = episodes.iter().find(|ep| ep.guid == episode_id).and_then(|ep| ep.duration);
+        match duration {
+            Some(duration) if timestamp > duration => Err(format!(
+                "Le minutage {} dépasse la durée de l'épisode ({})",
+                feed::format_clock(timestamp), feed::format_clock(duration)
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    shows: Arc<Vec<ShowState>>,
+    auth: Arc<Auth>,
+    hosts: Arc<HostRegistry>,
+    categories: Arc<CategoryRegistry>,
+    search: Arc<SearchIndex>,
+    positions: Arc<PositionStore>,
+    /// Chapitres et transcriptions déjà téléchargés.
+    extras: Arc<ExtrasCache>,
+    /// Propositions récentes des visiteurs sans compte.
+    submissions: Arc<Throttle>,
+    /// Échecs de connexion récents, par adresse.
+    logins: Arc<Throttle>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Recommendation {
-    pub chroniqueurs: Vec<String>,
-    pub titre: String,
-    pub lien: Option<String>,
-    pub type_media: String, // film, livre, chaîne youtube/twitch, etc.
-    pub description: String,
+impl AppState {
+    /// Émission désignée par son slug ; sans slug, la première émission configurée.
+    fn show(&self, slug: Option<&str>) -> Option<&ShowState> {
+        match slug {
+            Some(slug) => self.shows.iter().find(|s| s.show.slug == slug),
+            None => self.shows.first(),
+        }
+    }
 }
 
-type RecommendationsStore = Arc<Mutex<HashMap<String, Vec<Recommendation>>>>;
+fn show_not_found() -> Response {
+    (StatusCode::NOT_FOUND, "Émission inconnue").into_response()
+}
 
+/// Champs saisis dans le modal, communs à l'ajout et à la modification.
 #[derive(Deserialize)]
-struct RecommendationForm {
-    episode_title: String,
-    chroniqueurs: String, // séparés par des virgules
+struct RecommendationFields {
+    /// Identifiants choisis dans le select multiple (un paramètre par chroniqueur).
+    #[serde(default, deserialize_with = "one_or_many")]
+    chroniqueurs: Vec<String>,
     titre: String,
     lien: Option<String>,
     type_media: String,
     description: String,
+    /// Minutage saisi (`34:12`, `1:02:03` ou un nombre de secondes), vide s'il n'est pas indiqué.
+    #[serde(default)]
+    timestamp: String,
 }
 
-async fn fetch_episodes() -> Vec<Episode> {
-    let url = "https://feeds.acast.com/public/shows/portes-a-potes-pap";
-    let xml = reqwest::get(url).await.unwrap().text().await.unwrap();
-    let mut reader = Reader::from_str(&xml);
-    let mut buf = Vec::new();
-    let mut episodes = Vec::new();
-    let mut in_item = false;
-    let mut title = String::new();
-    let mut date = String::new();
-    let mut description = String::new();
-    let mut image_url = String::new();
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) if e.name().as_ref() == b"item" => {
-                in_item = true;
-                title.clear(); date.clear(); description.clear(); image_url.clear();
-            }
-            Ok(Event::End(ref e)) if e.name().as_ref() == b"item" => {
-                if in_item {
-                    episodes.push(Episode {
-                        title: title.clone(),
-                        date: date.clone(),
-                        description: description.clone(),
-                        image_url: if image_url.is_empty() {
-                            "https://via.placeholder.com/350x200?text=No+Image".to_string()
-                        } else {
-                            image_url.clone()
-                        },
-                    });
-                }
-                in_item = false;
-            }
-            Ok(Event::Start(ref e)) if in_item && e.name().as_ref() == b"title" => {
-                title = reader.read_text(e.name()).unwrap_or_default().trim().to_string();
-            }
-            Ok(Event::Start(ref e)) if in_item && e.name().as_ref() == b"pubDate" => {
-                date = reader.read_text(e.name()).unwrap_or_default().trim().to_string();
+impl RecommendationFields {
+    fn into_input(self) -> Result<RecommendationInput, &'static str> {
+        let timestamp = match self.timestamp.trim() {
+            "" => None,
+            text => Some(feed::parse_duration(text).ok_or("Minutage invalide : indiquez par exemple 34:12")?),
+        };
+        Ok(RecommendationInput {
+            chroniqueurs: self.chroniqueurs,
+            titre: self.titre,
+            lien: self.lien,
+            type_media: self.type_media,
+            description: self.description,
+            timestamp,
+        })
+    }
+}
+
+/// Un paramètre répété arrive en liste, un paramètre seul en chaîne (les champs aplatis par
+/// `#[serde(flatten)]` ne savent pas faire la conversion eux-mêmes).
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+/// Réponse pour une erreur de stockage ; les erreurs techniques sont journalisées.
+fn store_error(e: StoreError) -> Response {
+    let status = match e {
+        StoreError::NotFound => StatusCode::NOT_FOUND,
+        StoreError::Conflict => StatusCode::CONFLICT,
+        StoreError::Io(_) | StoreError::Json(_) | StoreError::Corrupt(..) | StoreError::Sqlite(_) => {
+            eprintln!("Erreur de stockage : {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Erreur lors de l'enregistrement, réessayez plus tard").into_response();
+        }
+    };
+    (status, e.to_string()).into_response()
+}
+
+/// Réponse d'erreur si l'utilisateur ne peut pas modifier ou supprimer la recommandation `id`.
+fn modification_refused(show: &ShowState, user: &CurrentUser, id: uuid::Uuid) -> Option<Response> {
+    match show.store.get(id) {
+        Ok(rec) => user.user.check_can_modify(&rec).err().map(IntoResponse::into_response),
+        Err(e) => Some(store_error(e)),
+    }
+}
+
+fn invalid_form(message: &str) -> Response {
+    (StatusCode::UNPROCESSABLE_ENTITY, message.to_string()).into_response()
+}
+
+/// Valide les champs du modal ; les chroniqueurs et la catégorie doivent exister, et le
+/// minutage tenir dans l'épisode. Une suggestion d'auditeur (`listener`) n'a pas de chroniqueur.
+fn validated_input(
+    state: &AppState,
+    show: &ShowState,
+    episode_id: &str,
+    fields: RecommendationFields,
+    listener: bool,
+) -> Result<RecommendationInput, String> {
+    let input = fields.into_input()?;
+    let input = if listener { input.validated_suggestion()? } else { input.validated()? };
+    state.hosts.check_known(&input.chroniqueurs)?;
+    state.categories.check_known(&input.type_media)?;
+    show.check_timestamp(episode_id, input.timestamp)?;
+    Ok(input)
+}
+
+#[derive(Deserialize)]
+struct RecommendationForm {
+    show: Option<String>,
+    csrf_token: String,
+    episode_id: String,
+    /// Champ invisible : seuls les robots le remplissent.
+    #[serde(default)]
+    site_web: String,
+    #[serde(flatten)]
+    fields: RecommendationFields,
+}
+
+#[derive(Deserialize)]
+struct EditRecommendationForm {
+    show: Option<String>,
+    csrf_token: String,
+    id: uuid::Uuid,
+    /// Date de modification vue par le client ; si elle a changé depuis, la modification est refusée.
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(flatten)]
+    fields: RecommendationFields,
+}
+
+async fn edit_recommendation(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    MultiForm(form): MultiForm<EditRecommendationForm>,
+) -> Response {
+    if let Err(e) = user.check_csrf(&form.csrf_token) {
+        return e.into_response();
+    }
+    let Some(show) = state.show(form.show.as_deref()) else {
+        return show_not_found();
+    };
+    let rec = match show.store.get(form.id) {
+        Ok(rec) => rec,
+        Err(e) => return store_error(e),
+    };
+    if let Err(e) = user.user.check_can_modify(&rec) {
+        return e.into_response();
+    }
+    // Le formulaire de modification n'envoie pas l'épisode : on le retrouve pour vérifier le minutage
+    let episode_id = match show.store.episode_of(form.id) {
+        Ok(episode_id) => episode_id,
+        Err(e) => return store_error(e),
+    };
+    // Une suggestion d'auditeur reste sans chroniqueur tant qu'on ne lui en attribue pas
+    let listener = rec.chroniqueurs.is_empty() && form.fields.chroniqueurs.iter().all(|c| c.trim().is_empty());
+    let input = match validated_input(&state, show, &episode_id, form.fields, listener) {
+        Ok(input) => input,
+        Err(message) => return invalid_form(&message),
+    };
+    match show.store.update(form.id, form.updated_at, input) {
+        Ok(_) => (StatusCode::OK, "").into_response(),
+        Err(e) => store_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct DeleteRecommendationForm {
+    show: Option<String>,
+    csrf_token: String,
+    id: uuid::Uuid,
+}
+
+/// Noms (déjà en HTML) des chroniqueurs d'une recommandation ; une suggestion d'auditeur n'en a pas.
+fn chroniqueurs_text(names: &[String]) -> String {
+    if names.is_empty() {
+        "Suggestion d'un auditeur".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// Réponse à une proposition en attente de modération.
+const SUGGESTION_THANKS: &str = "Merci ! Votre suggestion sera publiée après relecture.";
+
+/// Ajout depuis le modal : publié directement pour les chroniqueurs, en attente de modération
+/// pour les invités et les auditeurs sans compte.
+async fn add_recommendation(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    user: Option<CurrentUser>,
+    MultiForm(form): MultiForm<RecommendationForm>,
+) -> Response {
+    match &user {
+        Some(user) => {
+            if let Err(e) = user.check_csrf(&form.csrf_token) {
+                return e.into_response();
             }
-            Ok(Event::Start(ref e)) if in_item && e.name().as_ref() == b"description" => {
-                description = reader.read_text(e.name()).unwrap_or_default().trim().to_string();
+        }
+        // Sans session, il n'y a pas de compte à protéger : la suggestion part en modération,
+        // mais un robot ne doit pas pouvoir remplir la file
+        None => {
+            if !form.site_web.is_empty() {
+                return (StatusCode::ACCEPTED, SUGGESTION_THANKS).into_response();
             }
-            Ok(Event::Empty(ref e)) if in_item && e.name().as_ref() == b"itunes:image" => {
-                for attr in e.attributes().flatten() {
-                    if attr.key.as_ref() == b"href" {
-                        image_url = attr.unescape_value().unwrap_or_default().trim().to_string();
-                    }
-                }
+            if let Err(wait) = state.submissions.check(&headers, peer) {
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(axum::http::header::RETRY_AFTER, wait.to_string())],
+                    "Trop de propositions envoyées depuis cette adresse : réessayez dans quelques minutes.",
+                ).into_response();
             }
-            Ok(Event::Eof) => break,
-            _ => {}
         }
-        buf.clear();
     }
-    episodes
+    let Some(show) = state.show(form.show.as_deref()) else {
+        return show_not_found();
+    };
+    // Comme l'API : seulement pour un épisode du flux, sinon le minutage ne serait pas vérifié
+    let episodes = show.feed.episodes();
+    if episodes.is_empty() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Flux du podcast indisponible").into_response();
+    }
+    if !episodes.iter().any(|ep| ep.guid == form.episode_id) {
+        return (StatusCode::NOT_FOUND, "Épisode inconnu").into_response();
+    }
+    let publish = user.as_ref().is_some_and(|u| u.user.can_publish());
+    let status = if publish { Status::Approved } else { Status::Pending };
+    // Sans compte, la suggestion n'est attribuée à aucun chroniqueur
+    let rec = match validated_input(&state, show, &form.episode_id, form.fields, user.is_none()) {
+        Ok(input) => input.into_recommendation(status),
+        Err(message) => return invalid_form(&message),
+    };
+    if let Err(e) = show.store.insert(&form.episode_id, &rec) {
+        return store_error(e);
+    }
+    if !publish {
+        return (StatusCode::ACCEPTED, SUGGESTION_THANKS).into_response();
+    }
+    // La page se recharge pour afficher la recommandation à sa place
+    StatusCode::CREATED.into_response()
 }
 
-async fn add_recommendation(
-    State(store): State<RecommendationsStore>,
-    Form(form): Form<RecommendationForm>,
-) -> Html<String> {
-    let mut map = store.lock().unwrap();
-    let chroniqueurs: Vec<String> = form.chroniqueurs.split(',').map(|s| s.trim().to_string()).collect();
-    let rec = Recommendation {
-        chroniqueurs,
-        titre: form.titre,
-        lien: form.lien,
-        type_media: form.type_media,
-        description: form.description,
-    };
-    let episode_title = form.episode_title.clone();
-    map.entry(episode_title.clone()).or_default().push(rec);
-    save_recommendations(&map); // Sauvegarde après ajout
-    let recos = map.get(&form.episode_title).unwrap();
-    let last_reco = recos.last().unwrap();
-    let chroniqueurs = last_reco.chroniqueurs.join(", ");
-    let titre = if let Some(lien) = &last_reco.lien {
-        format!("<a href='{}' target='_blank'>{}</a>", lien, last_reco.titre)
-    } else {
-        last_reco.titre.clone()
+async fn delete_recommendation(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Form(form): Form<DeleteRecommendationForm>,
+) -> Response {
+    if let Err(e) = user.check_csrf(&form.csrf_token) {
+        return e.into_response();
+    }
+    let Some(show) = state.show(form.show.as_deref()) else {
+        return show_not_found();
     };
+    // Une recommandation déjà supprimée (autre onglet, autre personne) renvoie 404 sans rien retirer d'autre
+    if let Some(response) = modification_refused(show, &user, form.id) {
+        return response;
+    }
+    match show.store.delete(form.id) {
+        Ok(()) => (StatusCode::OK, "").into_response(),
+        Err(e) => store_error(e),
+    }
+}
+
+/// Page affichée quand aucun épisode n'a encore pu être lu depuis le flux.
+fn feed_unavailable_page(show: &ShowState) -> Response {
+    let error = show.feed.last_error().unwrap_or_else(|| "premier chargement du flux en cours".to_string());
     let html = format!(
-        "<div class='reco'><div class='reco-header'><b>{}</b> <span class='reco-type'>[{}]</span></div><div class='reco-chroniqueurs'>{}</div><div class='reco-desc'>{}</div></div>",
-        titre, last_reco.type_media, chroniqueurs, last_reco.description
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <meta http-equiv="refresh" content="{retry}">
+            <title>{name} - Flux indisponible</title>
+        </head>
+        <body style="font-family:sans-serif;display:flex;align-items:center;justify-content:center;min-height:100vh;margin:0;background:#f3f6fa;">
+            <div style="background:#fff;border-radius:18px;box-shadow:0 2px 16px #0002;padding:2em 2.5em;max-width:520px;text-align:center;">
+                <h1>{name}</h1>
+                <p>Les épisodes sont momentanément indisponibles : le flux du podcast ne répond pas.</p>
+                <p style="color:#888;font-size:0.9em;">{error}</p>
+                <p>La page se rechargera automatiquement dans {retry} secondes, ou <a href="{path}">réessayez maintenant</a>.</p>
+            </div>
+        </body>
+        </html>"#,
+        name = htmlescape::encode_minimal(&show.show.name),
+        path = show.page_path(),
+        retry = FEED_RETRY_AFTER_SECS,
+        error = htmlescape::encode_minimal(&error),
     );
-    Html(html)
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(axum::http::header::RETRY_AFTER, FEED_RETRY_AFTER_SECS.to_string())],
+        Html(html),
+    )
+        .into_response()
 }
 
-async fn delete_recommendation(
-    State(store): State<RecommendationsStore>,
-    Form(params): Form<std::collections::HashMap<String, String>>,
-) -> impl IntoResponse {
-    let mut map = store.lock().unwrap();
-    if let (Some(ep), Some(idx_str)) = (params.get("episode_title"), params.get("idx")) {
-        if let Ok(idx) = idx_str.parse::<usize>() {
-            if let Some(list) = map.get_mut(ep) {
-                if idx < list.len() {
-                    list.remove(idx);
-                    save_recommendations(&map);
-                }
-            }
+/// Boutons d'écoute de l'émission : plateformes renseignées, puis flux RSS.
+fn listen_links(show: &Show) -> String {
+    let platforms = [
+        (&show.links.apple, "apple", "Apple Podcasts", "https://upload.wikimedia.org/wikipedia/commons/thumb/e/e7/Podcasts_%28iOS%29.svg/300px-Podcasts_%28iOS%29.svg.png"),
+        (&show.links.spotify, "spotify", "Spotify", "https://upload.wikimedia.org/wikipedia/commons/thumb/8/84/Spotify_icon.svg/512px-Spotify_icon.svg.png?20220821125323"),
+        (&show.links.deezer, "deezer", "Deezer", "https://companieslogo.com/img/orig/DEEZR.PA-dbdcf2cf.png?t=1721547851"),
+    ];
+    let mut html = String::new();
+    for (url, class, label, logo) in platforms {
+        if let Some(url) = url {
+            html.push_str(&format!(
+                "<a href=\"{}\" target=\"_blank\" rel=\"noopener\" class=\"listen-btn {}\" title=\"{}\"><img src=\"{}\" alt=\"{}\" class=\"listen-logo\"></a>",
+                htmlescape::encode_attribute(url), class, label, logo, label
+            ));
         }
     }
-    (axum::http::StatusCode::OK, "")
+    html.push_str(&format!(
+        "<a href=\"{}\" target=\"_blank\" rel=\"noopener\" class=\"listen-btn acast\" title=\"Flux RSS\"><img src=\"https://upload.wikimedia.org/wikipedia/commons/thumb/4/46/Generic_Feed-icon.svg/256px-Generic_Feed-icon.svg.png?20120905025810\" alt=\"RSS\" class=\"listen-logo\"></a>",
+        htmlescape::encode_attribute(&show.feed_url)
+    ));
+    html
+}
+
+/// `/` : la page de l'émission s'il n'y en a qu'une, sinon l'index des émissions.
+async fn home_page(State(state): State<AppState>, user: Option<CurrentUser>, Query(filter): Query<RecoFilter>) -> Response {
+    if state.shows.len() == 1 {
+        return episodes_page(&state, &state.shows[0], user.as_ref(), &filter).await;
+    }
+    shows_index_page(&state)
+}
+
+async fn show_page(
+    State(state): State<AppState>,
+    user: Option<CurrentUser>,
+    UrlPath(slug): UrlPath<String>,
+    Query(filter): Query<RecoFilter>,
+) -> Response {
+    match state.show(Some(&slug)) {
+        Some(show) => episodes_page(&state, show, user.as_ref(), &filter).await,
+        None => show_not_found(),
+    }
 }
 
-async fn episodes_page(State(store): State<RecommendationsStore>) -> Html<String> {
-    let episodes = fetch_episodes().await;
-    let map = store.lock().unwrap();
+fn shows_index_page(state: &AppState) -> Response {
+    let cards: String = state.shows.iter().map(|s| {
+        let cover = s.show.branding.cover_url.as_deref().map(|url| format!(
+            "<img src='{}' alt='' style='width:88px;height:88px;border-radius:14px;object-fit:cover;box-shadow:0 2px 8px #0002;' />",
+            htmlescape::encode_attribute(url)
+        )).unwrap_or_default();
+        let tagline = s.show.branding.tagline.as_deref().map(|t| format!(
+            "<div style='color:#555;margin-top:0.3em;'>{}</div>", htmlescape::encode_minimal(t)
+        )).unwrap_or_default();
+        format!(
+            "<a class='card' href='/shows/{slug}' style='display:flex;align-items:center;gap:1em;color:#222;text-decoration:none;background:rgba(255,255,255,0.92);border-radius:18px;box-shadow:0 2px 16px #0002;padding:1.2em 1.5em;margin-bottom:1.5em;max-width:700px;width:100%;'>\
+                {cover}\
+                <div><div style='font-weight:bold;font-size:1.2em'>{name}</div>{tagline}</div>\
+            </a>",
+            slug = s.show.slug,
+            cover = cover,
+            name = htmlescape::encode_minimal(&s.show.name),
+            tagline = tagline,
+        )
+    }).collect();
+    Html(format!(
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Nos podcasts</title>
+        </head>
+        <body style="font-family:sans-serif;margin:0;background:#f3f6fa;">
+            <main style="display:flex;flex-direction:column;align-items:center;padding:2em 1em;">
+                <h1>Nos podcasts</h1>
+                {cards}
+            </main>
+        </body>
+        </html>"#,
+        cards = cards
+    )).into_response()
+}
+
+/// Page d'une émission ; les actions d'écriture ne sont proposées qu'aux chroniqueurs connectés.
+/// `filter` restreint les épisodes et recommandations affichés (voir `filters`).
+async fn episodes_page(state: &AppState, show: &ShowState, user: Option<&CurrentUser>, filter: &RecoFilter) -> Response {
+    let episodes = show.feed.episodes();
+    if episodes.is_empty() {
+        // Aucune copie en cache : la tâche de fond retente la lecture, la page ne fait qu'attendre
+        return feed_unavailable_page(show);
+    }
+    // Bandeau si le dernier rafraîchissement a échoué et que la copie affichée peut être ancienne
+    let stale_banner = match show.feed.last_error() {
+        Some(_) => "<div class='stale-banner' style='max-width:700px;margin:0 auto 1.5em auto;background:#fff3cd;color:#7a5b00;border-radius:12px;padding:0.7em 1.2em;box-shadow:0 2px 8px #0001;'>Le flux du podcast ne répond pas : les épisodes affichés peuvent ne pas être à jour. Rechargez la page un peu plus tard.</div>".to_string(),
+        None => String::new(),
+    };
+    let mut map = match show.store.all() {
+        Ok(map) => map,
+        Err(e) => return store_error(e),
+    };
+    // Les suggestions en attente ou refusées ne sont visibles que sur la page de modération
+    for list in map.values_mut() {
+        list.retain(|rec| rec.status == Status::Approved && filter.matches(rec));
+    }
+    // Épisodes retenus par les filtres : un filtre sur les recommandations masque les épisodes sans correspondance
+    let mut shown: Vec<&feed::Episode> = episodes.iter()
+        .filter(|ep| filter.matches_episode(ep))
+        .filter(|ep| !filter.filters_recommendations() || map.get(&ep.guid).is_some_and(|v| !v.is_empty()))
+        .collect();
+    if filter.sort() == Sort::Episode {
+        shown.sort_by_key(|ep| filters::episode_order(ep));
+    }
     // Options pour le select du modal global
     let mut episode_options = String::new();
-    for ep in &episodes {
-        episode_options.push_str(&format!("<option value=\"{}\">{}</option>", ep.title, ep.title));
+    for ep in episodes.iter() {
+        episode_options.push_str(&format!(
+            "<option value=\"{}\">{}</option>",
+            htmlescape::encode_attribute(&ep.guid),
+            htmlescape::encode_minimal(&ep.title)
+        ));
     }
+    // Chroniqueurs du registre ; celui du compte connecté est présélectionné
+    let own_host = user.and_then(|u| u.user.host.as_deref());
+    let hosts = state.hosts.all();
+    let host_options: String = hosts.iter().map(|host| format!(
+        "<option value='{}'{}>{}</option>",
+        htmlescape::encode_attribute(&host.id),
+        if own_host == Some(host.id.as_str()) { " selected" } else { "" },
+        htmlescape::encode_minimal(&host.name)
+    )).collect();
+    let hosts_hint = if hosts.is_empty() {
+        "Aucun chroniqueur enregistré : un administrateur doit d'abord les ajouter."
+    } else {
+        "Ctrl (ou Cmd) + clic pour en choisir plusieurs."
+    };
+    // Un auditeur sans compte propose en son nom : pas de choix de chroniqueur
+    let hosts_field = if user.is_some() {
+        format!(
+            "<label for='chroniqueurs-global'>Chroniqueurs</label>\
+            <select id='chroniqueurs-global' name='chroniqueurs' multiple required size='4'>{}</select>\
+            <div style='color:#888;font-size:0.85em;'>{}</div>",
+            host_options, hosts_hint
+        )
+    } else {
+        String::new()
+    };
+    // Les invités et les auditeurs proposent des suggestions, publiées après modération
+    let add_label = match user {
+        Some(user) if user.user.can_publish() => "Ajouter une recommandation",
+        _ => "Proposer une recommandation",
+    };
     let global_modal = format!(r#"
-    <button id='open-global-reco-modal' class='add-reco-btn'>Ajouter une recommandation</button>
-    <div class='modal-bg' id='global-reco-modal' style='display:none;visibility:hidden;' aria-hidden='true'>
+    <button id='open-global-reco-modal' class='add-reco-btn'>{add_label}</button>
+    <div class='modal-bg' id='global-reco-modal' data-add-title='{add_label}' style='display:none;visibility:hidden;' aria-hidden='true'>
         <div class='modal'>
             <button class='close-modal' title='Fermer'>&times;</button>
-            <h3>Ajouter une recommandation</h3>
+            <h3>{add_label}</h3>
             <form class='reco-form' method='post' action='/add_reco' autocomplete='off'>
-                <label for='episode_title'>Épisode concerné</label>
-                <select id='episode_title' name='episode_title' required>{}</select>
+                <input type='hidden' name='show' value='{slug}'>
+                <input type='hidden' name='csrf_token' value='{csrf}'>
+                <input type='hidden' name='id' disabled>
+                <input type='hidden' name='updated_at' disabled>
+                <div aria-hidden='true' style='position:absolute;left:-10000px;'>
+                    <label for='site-web'>Site web</label>
+                    <input id='site-web' name='site_web' tabindex='-1' autocomplete='off'>
+                </div>
+                <label for='episode_id'>Épisode concerné</label>
+                <select id='episode_id' name='episode_id' required>{episode_options}</select>
                 <label for='titre-global'>Titre</label>
                 <input id='titre-global' name='titre' placeholder='Titre' required autocomplete='off'>
                 <label for='lien-global'>Lien (optionnel)</label>
                 <input id='lien-global' name='lien' placeholder='Lien' autocomplete='off'>
-                <label for='chroniqueurs-global'>Chroniqueurs (séparés par des virgules)</label>
-                <input id='chroniqueurs-global' name='chroniqueurs' placeholder='Chroniqueurs' required autocomplete='off'>
+                {hosts_field}
                 <label for='type_media-global'>Type</label>
-                <select id='type_media-global' name='type_media' required>
-                    <option value='film'>Film</option>
-                    <option value='livre'>Livre</option>
-                    <option value='chaine'>Chaîne</option>
-                    <option value='compte'>Compte</option>
-                    <option value='musique'>Musique</option>
-                    <option value='série'>Série</option>
-                    <option value='jeu'>Jeu</option>
-                    <option value='autre'>Autre</option>
-                </select>
+                <select id='type_media-global' name='type_media' required>{type_options}</select>
                 <label for='description-global'>Description</label>
                 <textarea id='description-global' name='description' placeholder='Description' required autocomplete='off'></textarea>
+                <label for='timestamp-global'>Minutage dans l'épisode (optionnel)</label>
+                <input id='timestamp-global' name='timestamp' placeholder='34:12' pattern='[0-9:]*' autocomplete='off'>
                 <button type='submit'>Valider</button>
                 <div class='reco-confirm' style='min-height:1.2em;'></div>
             </form>
         </div>
     </div>
-    "#, episode_options);
+    "#,
+        add_label = add_label,
+        slug = show.show.slug,
+        csrf = user.map(|u| u.csrf_token.as_str()).unwrap_or(""),
+        episode_options = episode_options,
+        hosts_field = hosts_field,
+        type_options = state.categories.all().iter()
+            .map(|c| format!(
+                "<option value='{}'>{} {}</option>",
+                htmlescape::encode_attribute(&c.id), htmlescape::encode_minimal(&c.icon), htmlescape::encode_minimal(&c.label)
+            ))
+            .collect::<String>(),
+    );
     // Génération des cartes épisodes
-    let mut cards = String::new();
-    for ep in &episodes {
+    let mut rendered: Vec<(&feed::Episode, String)> = Vec::new();
+    for ep in shown.iter().copied() {
         if ep.title.trim().is_empty() {
             continue;
         }
-        // Nettoie le titre pour affichage ET pour la clé de recherche dans le HashMap
-        let raw_title = ep.title.replace("<![CDATA[", "").replace("]]>" , "").trim().to_string();
+        let raw_title = ep.title.trim().to_string();
         let safe_title = htmlescape::encode_minimal(&raw_title);
-        let safe_title_attr = safe_title.replace("\"", "&quot;").replace("'", "&#39;");
-        // Nettoie la description (supprime CDATA, conserve le HTML)
-        let mut desc = ep.description
-            .replace("<![CDATA[", "")
-            .replace("]]>" , "")
-            .replace("&nbsp;", " ")
-            .replace("&amp;", "&")
-            .replace("&quot;", "\"")
-            .replace("&lt;", "<")
-            .replace("&gt;", ">")
-            .trim()
-            .to_string();
-        // Supprime la mention Acast si présente
-        if let Some(idx) = desc.find("Hébergé par Acast") {
-            desc.truncate(idx);
-            desc = desc.trim_end().to_string();
-        }
+        let desc = episode_description(ep);
         let img_tag = if ep.image_url.contains("placeholder.com") {
             String::new()
         } else {
             format!("<img class='episode-img' src='{}' alt='Image épisode' style='width:88px;height:88px;border-radius:14px;object-fit:contain;background:#fff;box-shadow:0 2px 8px #0002;' />", ep.image_url)
         };
-        let data_ep = safe_title_attr.clone();
-        // Utilise le titre nettoyé comme clé pour le HashMap
-        let recos_html = map.get(&raw_title).map(|v| {
-            v.iter().enumerate().map(|(idx, rec)| {
-                // Catégories courtes et fusionnées
-                let type_label = match rec.type_media.to_lowercase().as_str() {
-                    "film" => "Film",
-                    "livre" => "Livre",
-                    "chaine youtube" | "chaine twitch" => "Chaîne",
-                    "compte instagram" | "compte tiktok" => "Compte",
-                    "musique" => "Musique",
-                    "série" => "Série",
-                    "jeu" => "Jeu",
-                    _ => "Autre",
-                };
+        let data_ep = htmlescape::encode_attribute(&ep.guid);
+        let recos_html = map.get(&ep.guid).map(|v| {
+            v.iter().map(|rec| {
+                let names: Vec<String> = rec.chroniqueurs.iter().map(|id| format!(
+                    "<a href='/chroniqueurs/{}' style='color:inherit;text-decoration:none;'>{}</a>",
+                    htmlescape::encode_attribute(id),
+                    htmlescape::encode_minimal(&state.hosts.display_name(id))
+                )).collect();
                 let chroniqueurs_html = format!(
                     "<span style='color:#9147ff;font-size:0.97em;font-style:italic;font-weight:600;border-radius:8px;padding:0.13em 0.7em 0.13em 0.7em;min-width:70px;text-align:left;letter-spacing:0.01em;background:none;'>{}</span>",
-                    htmlescape::encode_minimal(&rec.chroniqueurs.join(", "))
-                );
-                let type_color = match type_label {
-                    "Film" => "#ffb347",
-                    "Livre" => "#a3d977",
-                    "Chaîne" => "#ff5e5e",
-                    "Compte" => "#e1306c",
-                    "Musique" => "#1db954",
-                    "Série" => "#4a90e2",
-                    "Jeu" => "#f6c3ff",
-                    _ => "#b88a2a",
-                };
-                let type_bulle = format!(
-                    "<span class='reco-type' style='background:{};color:#fff;font-size:1em;font-style:normal;margin-left:0.1em;border-radius:50px;padding:0.18em 1.2em;box-shadow:0 2px 8px #0001;display:inline-block;letter-spacing:0.01em;min-width:70px;text-align:center;font-weight:600;vertical-align:middle;'>{}</span>",
-                    type_color, type_label
+                    chroniqueurs_text(&names)
                 );
+                let type_bulle = categories::badge(&state.categories.display(&rec.type_media));
+                let timestamp_chip = rec.timestamp.map(|t| timestamp_chip(ep, t)).unwrap_or_default();
                 let desc_compact = format!(
                     "<div class='reco-desc' style='font-size:0.98em;color:#222;line-height:1.4;margin:0.5em 0 0.1em 0;padding-left:0.2em;font-family:sans-serif;font-style:normal;'>{}</div>",
                     htmlescape::encode_minimal(&rec.description)
@@ -266,22 +617,40 @@ async fn episodes_page(State(store): State<RecommendationsStore>) -> Html<String
                 } else {
                     htmlescape::encode_minimal(&rec.titre)
                 };
+                let actions = if user.is_some_and(|u| u.user.can_modify(rec)) {
+                    format!(
+                        "<button class='edit-reco-btn' data-id='{id}' data-ep='{data_ep}' data-updated-at='{updated_at}' data-titre='{edit_titre}' data-lien='{edit_lien}' data-chroniqueurs='{edit_chroniqueurs}' data-type-media='{edit_type}' data-description='{edit_description}' data-timestamp='{edit_timestamp}' title='Modifier' style='background:none;border:none;color:#4a90e2;font-size:1.2em;cursor:pointer;'><span style='font-size:1.1em;'>&#9998;</span></button>\
+                        <button class='delete-reco-btn' data-id='{id}' title='Supprimer' style='background:none;border:none;color:#c00;font-size:1.2em;cursor:pointer;'><span style='font-size:1.2em;'>&#10006;</span></button>",
+                        id=rec.id,
+                        data_ep=&data_ep,
+                        updated_at=rec.updated_at.to_rfc3339(),
+                        edit_titre=htmlescape::encode_attribute(&rec.titre),
+                        edit_lien=htmlescape::encode_attribute(rec.lien.as_deref().unwrap_or("")),
+                        edit_chroniqueurs=htmlescape::encode_attribute(&rec.chroniqueurs.join(",")),
+                        edit_type=htmlescape::encode_attribute(&rec.type_media),
+                        edit_description=htmlescape::encode_attribute(&rec.description),
+                        edit_timestamp=rec.timestamp.map(feed::format_clock).unwrap_or_default(),
+                    )
+                } else {
+                    String::new()
+                };
                 format!(
                     "<div class='reco-card' style='background:#eaf6fb;border-radius:18px;padding:1.2em 1.3em 1.1em 1.3em;margin-bottom:1.1em;box-shadow:0 2px 8px #0001;position:relative;overflow:hidden;'>\
                         <div class='reco-header' style='display:flex;align-items:center;justify-content:space-between;margin-bottom:0.5em;'>\
                             <div style='flex:1;display:flex;align-items:center;gap:0.7em;'>\
                                 {chroniqueurs_html}\
                                 <div style='flex:1;text-align:center;'>{titre_html}</div>\
+                                {timestamp_chip}\
                                 {type_bulle}\
                             </div>\
-                            <button class='delete-reco-btn' data-ep='{data_ep}' data-idx='{idx}' title='Supprimer' style='background:none;border:none;color:#c00;font-size:1.2em;cursor:pointer;'><span style='font-size:1.2em;'>&#10006;</span></button>\
+                            {actions}\
                         </div>\
                         {desc_compact}\
                     </div>",
-                    idx=idx,
+                    actions=actions,
                     titre_html=titre_html,
                     type_bulle=type_bulle,
-                    data_ep=&data_ep,
+                    timestamp_chip=timestamp_chip,
                     chroniqueurs_html=chroniqueurs_html,
                     desc_compact=desc_compact
                 )
@@ -306,27 +675,68 @@ async fn episodes_page(State(store): State<RecommendationsStore>) -> Html<String
             String::new()
         };
         let titre_affiche = if raw_title.trim().is_empty() { "Épisode sans titre".to_string() } else { safe_title.clone() };
-        cards.push_str(&format!(
+        rendered.push((ep, format!(
             "<div class='card' style='background:#f3f6fa;border-radius:22px;box-shadow:0 2px 12px #0001;padding:1.2em 1.1em 1.2em 1.1em;margin-bottom:1.5em;'>\
                 <div class='card-top' style='display:flex;align-items:center;gap:1em;'>\
                     <div class='img-col'>{img}</div>\
                     <div class='info-col' style='flex:1;'>\
-                        <div style='font-weight:bold;font-size:1.1em'>{titre}</div>\
-                        <div class='date' style='color:#888;font-size:0.95em'>{date}</div>\
+                        <a href='{episode_path}' style='font-weight:bold;font-size:1.1em;color:inherit;text-decoration:none;'>{titre}</a>\
+                        <div class='date' style='color:#888;font-size:0.95em'>{date}{meta}</div>\
                     </div>\
                 </div>\
                 <div class='desc' style='margin-top:0.7em'>{desc}</div>\
+                {player}\
                 {show_recos_btn}\
                 {recos_block}\
             </div>",
             img=img_tag,
             titre=titre_affiche,
+            episode_path=htmlescape::encode_attribute(&episodes::episode_path(show, ep)),
             date=htmlescape::encode_minimal(&ep.date),
+            meta=episode_meta(ep),
             desc=desc,
+            player=inline_player(ep),
             show_recos_btn=show_recos_btn,
             recos_block=recos_block
-        ));
+        )));
+    }
+    let has_audio = rendered.iter().any(|(ep, _)| ep.audio.is_some());
+    let (mut cards, season_nav) = season_sections(rendered);
+    if cards.is_empty() {
+        cards.push_str("<p style='text-align:center;background:rgba(255,255,255,0.92);border-radius:14px;padding:1em;'>Aucun épisode ne correspond à ces filtres.</p>");
     }
+    let filter_form = filter.form(state, &show.page_path(), &filters::seasons(episodes.iter()), false);
+    let background = match &show.show.branding.cover_url {
+        Some(url) => format!("url('{}') center center/cover no-repeat", htmlescape::encode_attribute(url)),
+        None => "#3a3f47".to_string(),
+    };
+    let back_link = if show.has_siblings {
+        "<a href='/' style='color:#fff;'>&larr; Tous nos podcasts</a>"
+    } else {
+        ""
+    };
+    let page_path = show.page_path();
+    let account = match user {
+        Some(user) => format!(
+            "{moderation}<form method='post' action='/logout' style='display:inline;color:#fff;'>{name} \
+                <input type='hidden' name='csrf_token' value='{csrf}'><input type='hidden' name='next' value='{next}'>\
+                <button type='submit' style='background:none;border:none;color:#fff;text-decoration:underline;cursor:pointer;'>Déconnexion</button></form>",
+            moderation = if user.user.is_admin() {
+                "<a href='/admin/moderation' style='color:#fff;margin-right:1em;'>Modération</a>\
+                 <a href='/admin/chroniqueurs' style='color:#fff;margin-right:1em;'>Chroniqueurs</a>\
+                 <a href='/admin/categories' style='color:#fff;margin-right:1em;'>Catégories</a>"
+            } else {
+                ""
+            },
+            name = htmlescape::encode_minimal(&user.user.name),
+            csrf = user.csrf_token,
+            next = htmlescape::encode_attribute(&page_path),
+        ),
+        None => format!(
+            "<a href='/login?next={}' style='color:#fff;'>Connexion chroniqueurs</a>",
+            htmlescape::encode_attribute(&page_path)
+        ),
+    };
     // JS pour la suppression dynamique des recommandations (DOM + backend)
     let js = r#"
     <script>
@@ -334,11 +744,27 @@ async fn episodes_page(State(store): State<RecommendationsStore>) -> Html<String
         // Ouvre le modal d'ajout de reco
         var openModalBtn = document.getElementById('open-global-reco-modal');
         var modalBg = document.getElementById('global-reco-modal');
+        var recoForm = document.querySelector('.reco-form');
+        function openModal() {
+            modalBg.style.display = 'flex';
+            modalBg.style.visibility = 'visible';
+            modalBg.setAttribute('aria-hidden', 'false');
+        }
+        // Bascule le modal entre ajout et modification (champs cachés id/updated_at, épisode figé)
+        function setModalMode(edit) {
+            recoForm.setAttribute('action', edit ? '/edit_reco' : '/add_reco');
+            recoForm.elements['id'].disabled = !edit;
+            recoForm.elements['updated_at'].disabled = !edit;
+            recoForm.elements['episode_id'].disabled = edit;
+            if(recoForm.elements['chroniqueurs']) recoForm.elements['chroniqueurs'].required = true;
+            modalBg.querySelector('h3').textContent = edit ? 'Modifier la recommandation' : modalBg.dataset.addTitle;
+            recoForm.querySelector('.reco-confirm').textContent = '';
+        }
         if(openModalBtn && modalBg) {
             openModalBtn.addEventListener('click', function() {
-                modalBg.style.display = 'flex';
-                modalBg.style.visibility = 'visible';
-                modalBg.setAttribute('aria-hidden', 'false');
+                recoForm.reset();
+                setModalMode(false);
+                openModal();
             });
             // Ferme le modal avec la croix
             var closeBtn = modalBg.querySelector('.close-modal');
@@ -374,37 +800,73 @@ async fn episodes_page(State(store): State<RecommendationsStore>) -> Html<String
                 }
             });
         });
+        // Ouvre le modal pré-rempli avec la recommandation à modifier
+        document.addEventListener('click', function(e) {
+            var btn = e.target && e.target.closest('.edit-reco-btn');
+            if(!btn) return;
+            setModalMode(true);
+            recoForm.elements['id'].value = btn.dataset.id;
+            recoForm.elements['updated_at'].value = btn.dataset.updatedAt;
+            recoForm.elements['episode_id'].value = btn.dataset.ep;
+            recoForm.elements['titre'].value = btn.dataset.titre;
+            recoForm.elements['lien'].value = btn.dataset.lien;
+            var chroniqueurs = btn.dataset.chroniqueurs.split(',');
+            Array.prototype.forEach.call(recoForm.elements['chroniqueurs'].options, function(option) {
+                option.selected = chroniqueurs.indexOf(option.value) !== -1;
+            });
+            // Une suggestion d'auditeur peut rester sans chroniqueur
+            recoForm.elements['chroniqueurs'].required = btn.dataset.chroniqueurs !== '';
+            recoForm.elements['type_media'].value = btn.dataset.typeMedia;
+            recoForm.elements['description'].value = btn.dataset.description;
+            recoForm.elements['timestamp'].value = btn.dataset.timestamp;
+            openModal();
+        });
         // Suppression dynamique d'une recommandation
         document.addEventListener('click', function(e) {
             if(e.target && e.target.closest('.delete-reco-btn')) {
                 var btn = e.target.closest('.delete-reco-btn');
-                var ep = btn.getAttribute('data-ep');
-                var idx = btn.getAttribute('data-idx');
+                var id = btn.getAttribute('data-id');
                 fetch('/delete_reco', {
                     method: 'POST',
                     headers: {'Content-Type': 'application/x-www-form-urlencoded'},
-                    body: 'episode_title=' + encodeURIComponent(ep) + '&idx=' + encodeURIComponent(idx)
-                }).then(r => r.ok ? Promise.resolve() : Promise.reject()).then(() => {
-                    var recoCard = btn.closest('.reco-card');
-                    if(recoCard) recoCard.remove();
+                    body: 'show=' + encodeURIComponent(document.body.dataset.show) + '&csrf_token=' + encodeURIComponent(document.body.dataset.csrf) + '&id=' + encodeURIComponent(id)
+                }).then(function(r) {
+                    if(r.ok) {
+                        var recoCard = btn.closest('.reco-card');
+                        if(recoCard) recoCard.remove();
+                    } else if(r.status === 404) {
+                        // Déjà supprimée ailleurs : on recharge pour afficher la liste à jour
+                        window.location.reload();
+                    }
                 });
             }
         });
-        // Soumission AJAX du formulaire d'ajout de reco
-        var addRecoForm = document.querySelector('.reco-form');
-        if (addRecoForm) {
-            addRecoForm.addEventListener('submit', function(e) {
+        // Soumission AJAX du formulaire d'ajout ou de modification de reco
+        if (recoForm) {
+            recoForm.addEventListener('submit', function(e) {
                 e.preventDefault();
                 var form = this;
                 var data = new URLSearchParams(new FormData(form));
-                fetch('/add_reco', {
+                fetch(form.getAttribute('action'), {
                     method: 'POST',
                     body: data,
                     headers: {
                         'Content-Type': 'application/x-www-form-urlencoded'
                     }
-                }).then(function() {
-                    window.location.reload();
+                }).then(function(r) {
+                    if(r.status === 202) {
+                        // Suggestion en attente de modération : rien ne change encore sur la page
+                        r.text().then(function(msg) {
+                            form.reset();
+                            form.querySelector('.reco-confirm').textContent = msg;
+                        });
+                    } else if(r.ok) {
+                        window.location.reload();
+                    } else {
+                        r.text().then(function(msg) {
+                            form.querySelector('.reco-confirm').textContent = msg || 'Erreur lors de l\'enregistrement';
+                        });
+                    }
                 });
             });
         }
@@ -418,7 +880,7 @@ async fn episodes_page(State(store): State<RecommendationsStore>) -> Html<String
         <head>
             <meta charset=\"UTF-8\">
             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">
-            <title>Portes à Potes - Recommandations</title>
+            <title>{name} - Recommandations</title>
             <link rel=\"stylesheet\" href=\"/static/styles.css\">
             <script src=\"/static/scripts.js\" defer></script>
             <style>
@@ -434,7 +896,7 @@ async fn episodes_page(State(store): State<RecommendationsStore>) -> Html<String
                     position: fixed;
                     top: 0; left: 0; right: 0; bottom: 0;
                     z-index: 0;
-                    background: url('https://assets.pippa.io/shows/cover/1678196289243-eb4dc05a818625489cad37a30940fd3b.jpeg') center center/cover no-repeat;
+                    background: {background};
                     filter: blur(18px) brightness(0.7);
                     width: 100vw;
                     height: 100vh;
@@ -624,70 +1086,562 @@ async fn episodes_page(State(store): State<RecommendationsStore>) -> Html<String
                 }}
             </style>
         </head>
-        <body>
+        <body data-show="{slug}" data-csrf="{csrf}">
             <div class='background-blur'></div>
             <div class='main-content'>
                 <header>
-                    <h1>Portes à Potes</h1>
+                    {back_link}
+                    <div style='float:right;'>{account}</div>
+                    <form method='get' action='/search' style='margin-top:0.6em;'>
+                        <input type='search' name='q' placeholder='Rechercher un épisode, une recommandation...' aria-label='Rechercher' style='padding:0.4em 0.8em;border-radius:2em;border:none;min-width:260px;'>
+                        <a href='/recommandations' style='color:#fff;margin-left:1em;'>Toutes les recommandations</a>
+                    </form>
+                    <h1>{name}</h1>
                 </header>
                 <div class="listen-links" style="max-width:600px;margin:0 auto 2em auto;display:flex;flex-wrap:wrap;gap:1em;justify-content:center;align-items:center;">
-                    <a href="https://podcasts.apple.com/fr/podcast/portes-%C3%A0-potes/id1676606425" target="_blank" rel="noopener" class="listen-btn apple" title="Apple Podcasts">
-                        <img src="https://upload.wikimedia.org/wikipedia/commons/thumb/e/e7/Podcasts_%28iOS%29.svg/300px-Podcasts_%28iOS%29.svg.png" alt="Apple Podcasts" class="listen-logo">
-                    </a>
-                    <a href="https://open.spotify.com/show/08mBuJPR173kee3Hj500ol?si=b40b642320db45af" target="_blank" rel="noopener" class="listen-btn spotify" title="Spotify">
-                        <img src="https://upload.wikimedia.org/wikipedia/commons/thumb/8/84/Spotify_icon.svg/512px-Spotify_icon.svg.png?20220821125323" alt="Spotify" class="listen-logo">
-                    </a>
-                    <a href="https://dzr.page.link/KSSbPUMwubqNqzgc6" target="_blank" rel="noopener" class="listen-btn deezer" title="Deezer">
-                        <img src="https://companieslogo.com/img/orig/DEEZR.PA-dbdcf2cf.png?t=1721547851" alt="Deezer" class="listen-logo">
-                    </a>
-                    <a href="https://feeds.acast.com/public/shows/portes-a-potes-pap" target="_blank" rel="noopener" class="listen-btn acast" title="Acast RSS">
-                        <img src="https://upload.wikimedia.org/wikipedia/commons/thumb/4/46/Generic_Feed-icon.svg/256px-Generic_Feed-icon.svg.png?20120905025810" alt="RSS" class="listen-logo">
-                    </a>
+                    {listen_links}
                 </div>
+                {stale_banner}
                 <div style='display:flex;justify-content:center;'>
                     {global_modal}
                 </div>
+                {filter_form}
+                {season_nav}
                 <main style='width:100%;max-width:900px;'>
                     {cards}
                 </main>
                 <footer>
-                    <p>&copy; 2023 Portes à Potes. Tous droits réservés.</p>
+                    <p>&copy; 2023 {name}. Tous droits réservés.</p>
                 </footer>
             </div>
+            {mini_player}
             {js}
+            {player_js}
         </body>
         </html>
         "#,
+        name=htmlescape::encode_minimal(&show.show.name),
+        slug=show.show.slug,
+        background=background,
+        back_link=back_link,
+        account=account,
+        csrf=user.map(|u| u.csrf_token.as_str()).unwrap_or(""),
+        listen_links=listen_links(&show.show),
+        stale_banner=stale_banner,
         global_modal=global_modal,
+        filter_form=filter_form,
+        season_nav=season_nav,
         cards=cards,
-        js=js
-    ))
+        js=js,
+        mini_player=if has_audio { MINI_PLAYER } else { "" },
+        player_js=if has_audio { PLAYER_JS } else { "" },
+    )).into_response()
+}
+
+/// Commandes de lecture d'une carte ; l'audio lui-même est joué par le mini-lecteur de la page.
+fn inline_player(ep: &feed::Episode) -> String {
+    let Some(audio) = &ep.audio else {
+        return String::new();
+    };
+    format!(
+        "<div class='inline-player' data-ep='{ep}' data-src='{src}' data-title='{title}' data-duration='{duration}' style='display:flex;align-items:center;gap:0.7em;margin-top:0.8em;'>\
+            <button class='play-btn' title='Écouter' style='background:#4a90e2;color:#fff;border:none;border-radius:50%;width:2.2em;height:2.2em;cursor:pointer;font-size:1em;'>&#9654;</button>\
+            <progress class='play-progress' max='1' value='0' style='flex:1;height:0.5em;'></progress>\
+            <span class='play-time' style='color:#888;font-size:0.9em;min-width:7em;'></span>\
+        </div>",
+        ep = htmlescape::encode_attribute(&ep.guid),
+        src = htmlescape::encode_attribute(&audio.url),
+        title = htmlescape::encode_attribute(&ep.title),
+        duration = ep.duration.unwrap_or(0),
+    )
+}
+
+/// Pastille « à 34:12 » d'une recommandation ; si l'épisode a un fichier audio, elle lance le
+/// lecteur à ce moment.
+fn timestamp_chip(ep: &feed::Episode, timestamp: u32) -> String {
+    let style = "background:#fff;color:#4a90e2;border:1px solid #4a90e2;border-radius:50px;padding:0.1em 0.7em;font-size:0.9em;white-space:nowrap;";
+    if ep.audio.is_some() {
+        format!(
+            "<button class='timestamp-chip' data-ep='{}' data-t='{}' title='Écouter ce passage' style='{}cursor:pointer;'>à {}</button>",
+            htmlescape::encode_attribute(&ep.guid), timestamp, style, feed::format_clock(timestamp)
+        )
+    } else {
+        format!("<span class='timestamp-chip' style='{}'>à {}</span>", style, feed::format_clock(timestamp))
+    }
+}
+
+/// Lecteur fixé en bas de page, qui continue de jouer pendant qu'on fait défiler les épisodes.
+const MINI_PLAYER: &str = "<div id='mini-player' style='display:none;position:fixed;left:0;right:0;bottom:0;z-index:20;background:rgba(34,34,34,0.96);color:#fff;padding:0.6em 1em;align-items:center;gap:1em;flex-wrap:wrap;box-shadow:0 -2px 12px #0004;'>\
+    <span class='mini-title' style='font-weight:bold;flex:1;min-width:10em;'></span>\
+    <audio id='player-audio' controls preload='none' style='flex:2;min-width:16em;'></audio>\
+    <label style='font-size:0.9em;'>Vitesse \
+        <select id='player-speed'><option value='0.75'>0,75×</option><option value='1' selected>1×</option><option value='1.25'>1,25×</option><option value='1.5'>1,5×</option><option value='1.75'>1,75×</option><option value='2'>2×</option></select>\
+    </label>\
+    <button id='player-close' title='Fermer le lecteur' style='background:none;border:none;color:#fff;font-size:1.4em;cursor:pointer;'>&times;</button>\
+</div>";
+
+/// Lecture, vitesse et reprise : la position de chaque épisode est gardée dans le navigateur
+/// et, pour les comptes connectés, envoyée à `/positions/{show}`.
+const PLAYER_JS: &str = r#"
+<script>
+document.addEventListener('DOMContentLoaded', function() {
+    var audio = document.getElementById('player-audio');
+    if(!audio) return;
+    var show = document.body.dataset.show;
+    var csrf = document.body.dataset.csrf;
+    var bar = document.getElementById('mini-player');
+    var speed = document.getElementById('player-speed');
+    var current = null;
+    var lastSaved = 0, lastSynced = 0;
+    function key(ep) { return 'pap-position:' + show + ':' + ep; }
+    function load(ep) {
+        try { return JSON.parse(localStorage.getItem(key(ep))); } catch(e) { return null; }
+    }
+    function store(ep, seconds, at) {
+        localStorage.setItem(key(ep), JSON.stringify({seconds: seconds, at: at || Date.now()}));
+    }
+    function pad(n) { return String(n).padStart(2, '0'); }
+    function fmt(s) {
+        s = Math.floor(s);
+        var h = Math.floor(s / 3600), m = Math.floor(s % 3600 / 60);
+        return (h ? h + ':' + pad(m) : m) + ':' + pad(s % 60);
+    }
+    function playerFor(ep) {
+        return Array.prototype.find.call(document.querySelectorAll('.inline-player'), function(el) { return el.dataset.ep === ep; });
+    }
+    // Barre de progression et « Reprendre à » d'une carte, d'après la position enregistrée
+    function refresh(el) {
+        var saved = load(el.dataset.ep);
+        var duration = Number(el.dataset.duration) || 0;
+        var seconds = saved ? saved.seconds : 0;
+        el.querySelector('.play-progress').value = duration ? seconds / duration : 0;
+        el.querySelector('.play-time').textContent = seconds > 0 ? 'Reprendre à ' + fmt(seconds) : '';
+    }
+    function sync(ep, seconds) {
+        if(!csrf) return;
+        lastSynced = Date.now();
+        fetch('/positions/' + encodeURIComponent(show), {
+            method: 'POST',
+            keepalive: true,
+            body: new URLSearchParams({episode_id: ep, seconds: seconds.toFixed(1), csrf_token: csrf})
+        }).catch(function() {});
+    }
+    function save(force) {
+        if(!current) return;
+        var seconds = audio.ended ? 0 : audio.currentTime;
+        store(current.ep, seconds);
+        lastSaved = Date.now();
+        if(force || Date.now() - lastSynced > 15000) sync(current.ep, seconds);
+    }
+    function setRate(rate) {
+        audio.defaultPlaybackRate = rate;
+        audio.playbackRate = rate;
+    }
+    var savedRate = Number(localStorage.getItem('pap-speed'));
+    if(savedRate) { speed.value = String(savedRate); setRate(savedRate); }
+    speed.addEventListener('change', function() {
+        setRate(Number(speed.value));
+        localStorage.setItem('pap-speed', speed.value);
+    });
+    // Lance un épisode, à la position enregistrée ou à `startAt` secondes
+    function play(el, startAt) {
+        if(!current || current.ep !== el.dataset.ep) {
+            save(true);
+            current = {ep: el.dataset.ep, el: el};
+            var saved = load(el.dataset.ep);
+            var start = startAt !== undefined ? startAt : (saved ? saved.seconds : 0);
+            audio.src = el.dataset.src;
+            setRate(Number(speed.value));
+            audio.addEventListener('loadedmetadata', function() {
+                if(start > 0 && (!audio.duration || start < audio.duration - 5)) audio.currentTime = start;
+            }, {once: true});
+            bar.querySelector('.mini-title').textContent = el.dataset.title;
+            bar.style.display = 'flex';
+            document.body.style.paddingBottom = bar.offsetHeight + 'px';
+        } else if(startAt !== undefined) {
+            audio.currentTime = startAt;
+        }
+        audio.play();
+    }
+    window.papPlay = function(ep, startAt) {
+        var el = playerFor(ep);
+        if(el) play(el, startAt);
+    };
+    document.addEventListener('click', function(e) {
+        var chip = e.target && e.target.closest('button.timestamp-chip');
+        if(chip) window.papPlay(chip.dataset.ep, Number(chip.dataset.t));
+    });
+    document.addEventListener('click', function(e) {
+        var btn = e.target && e.target.closest('.play-btn');
+        if(!btn) return;
+        var el = btn.closest('.inline-player');
+        if(current && current.ep === el.dataset.ep && !audio.paused) {
+            audio.pause();
+        } else {
+            play(el);
+        }
+    });
+    function setIcons() {
+        document.querySelectorAll('.inline-player').forEach(function(el) {
+            var playing = current && current.ep === el.dataset.ep && !audio.paused;
+            el.querySelector('.play-btn').innerHTML = playing ? '&#10074;&#10074;' : '&#9654;';
+        });
+    }
+    audio.addEventListener('play', setIcons);
+    audio.addEventListener('pause', function() { setIcons(); save(true); });
+    audio.addEventListener('ended', function() { save(true); if(current) refresh(current.el); });
+    audio.addEventListener('timeupdate', function() {
+        if(!current) return;
+        var duration = audio.duration || Number(current.el.dataset.duration) || 0;
+        current.el.querySelector('.play-progress').value = duration ? audio.currentTime / duration : 0;
+        current.el.querySelector('.play-time').textContent = fmt(audio.currentTime) + (duration ? ' / ' + fmt(duration) : '');
+        if(Date.now() - lastSaved > 5000) save(false);
+    });
+    window.addEventListener('pagehide', function() { if(current && !audio.paused) save(true); });
+    document.getElementById('player-close').addEventListener('click', function() {
+        audio.pause();
+        bar.style.display = 'none';
+        document.body.style.paddingBottom = '';
+        if(current) refresh(current.el);
+        current = null;
+        audio.removeAttribute('src');
+        setIcons();
+    });
+    document.querySelectorAll('.inline-player').forEach(refresh);
+    // Les positions du compte l'emportent sur celles du navigateur si elles sont plus récentes
+    if(csrf) {
+        fetch('/positions/' + encodeURIComponent(show)).then(function(r) { return r.ok ? r.json() : {}; }).then(function(positions) {
+            Object.keys(positions).forEach(function(ep) {
+                var saved = load(ep);
+                var at = Date.parse(positions[ep].updated_at);
+                if(!saved || saved.at < at) store(ep, positions[ep].seconds, at);
+            });
+            document.querySelectorAll('.inline-player').forEach(function(el) {
+                if(!current || current.ep !== el.dataset.ep) refresh(el);
+            });
+        }).catch(function() {});
+    }
+});
+</script>
+"#;
+
+/// Description à afficher : nettoyée (sans CDATA, HTML conservé) ; à défaut, le résumé iTunes.
+fn episode_description(ep: &feed::Episode) -> String {
+    let description = if ep.description.trim().is_empty() {
+        ep.summary.as_deref().map(htmlescape::encode_minimal).unwrap_or_default()
+    } else {
+        ep.description.clone()
+    };
+    let mut desc = description
+        .replace("<![CDATA[", "")
+        .replace("]]>" , "")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .trim()
+        .to_string();
+    // Supprime la mention Acast si présente
+    if let Some(idx) = desc.find("Hébergé par Acast") {
+        desc.truncate(idx);
+        desc = desc.trim_end().to_string();
+    }
+    desc
+}
+
+/// Durée, mention explicite et lien vers la page de l'épisode, à la suite de la date.
+fn episode_meta(ep: &feed::Episode) -> String {
+    let mut meta = String::new();
+    if let Some(duration) = ep.duration {
+        meta.push_str(&format!(" · <span class='duration'>{}</span>", feed::format_duration(duration)));
+    }
+    if ep.explicit {
+        meta.push_str(" · <span title='Contenu explicite' style='background:#555;color:#fff;border-radius:4px;padding:0 0.35em;font-size:0.85em;'>E</span>");
+    }
+    if let Some(link) = &ep.link {
+        meta.push_str(&format!(
+            " · <a href='{}' target='_blank' rel='noopener' style='color:#4a90e2;'>Page de l'épisode</a>",
+            htmlescape::encode_attribute(link)
+        ));
+    }
+    meta
+}
+
+/// Regroupe les cartes par saison, dans l'ordre où les saisons apparaissent, puis les bandes-annonces
+/// et bonus à part. Renvoie les sections et la navigation entre saisons (vide s'il n'y en a qu'une).
+fn season_sections(rendered: Vec<(&feed::Episode, String)>) -> (String, String) {
+    let mut seasons: Vec<(Option<u32>, String)> = Vec::new();
+    let mut extras = String::new();
+    for (ep, card) in rendered {
+        if ep.episode_type != feed::EpisodeType::Full {
+            extras.push_str(&card);
+            continue;
+        }
+        match seasons.iter_mut().find(|(season, _)| *season == ep.season) {
+            Some((_, cards)) => cards.push_str(&card),
+            None => seasons.push((ep.season, card)),
+        }
+    }
+    // Des titres de section seulement s'il y a de quoi distinguer
+    if seasons.len() <= 1 && extras.is_empty() {
+        return (seasons.pop().map(|(_, cards)| cards).unwrap_or_default(), String::new());
+    }
+    let heading = |id: &str, label: &str| format!(
+        "<h2 id='{}' class='season-heading' style='color:#fff;text-shadow:0 2px 8px #0008;margin:1.2em 0 0.6em 0;'>{}</h2>",
+        id, label
+    );
+    let mut sections = String::new();
+    let mut links = Vec::new();
+    for (season, cards) in &seasons {
+        let (id, label) = match season {
+            Some(n) => (format!("saison-{}", n), format!("Saison {}", n)),
+            None => ("autres-episodes".to_string(), "Autres épisodes".to_string()),
+        };
+        sections.push_str(&heading(&id, &label));
+        sections.push_str(cards);
+        links.push((id, label));
+    }
+    if !extras.is_empty() {
+        sections.push_str(&heading("bonus", "Bandes-annonces et bonus"));
+        sections.push_str(&extras);
+        links.push(("bonus".to_string(), "Bandes-annonces et bonus".to_string()));
+    }
+    let nav = format!(
+        "<nav class='season-nav' style='display:flex;flex-wrap:wrap;gap:0.6em;justify-content:center;margin:0 auto 1.5em auto;'>{}</nav>",
+        links.iter().map(|(id, label)| format!(
+            "<a href='#{}' style='background:rgba(255,255,255,0.92);color:#222;text-decoration:none;border-radius:50px;padding:0.3em 1em;box-shadow:0 2px 8px #0001;'>{}</a>",
+            id, label
+        )).collect::<String>()
+    );
+    (sections, nav)
 }
 
-fn save_recommendations(map: &HashMap<String, Vec<Recommendation>>) {
-    let json = serde_json::to_string_pretty(map).unwrap();
-    let _ = fs::write("recommandations.json", json);
+/// Rattache aux guid du flux les recommandations encore indexées par titre d'épisode.
+fn migrate_to_guids(show: &Show, store: &RecommendationsStore, episodes: &[feed::Episode]) -> Result<(), StoreError> {
+    let mut map = store.all()?;
+    let report = store::migrate_title_keys(&mut map, episodes);
+    for (title, guid) in &report.migrated {
+        println!("[{}] Recommandations de « {} » rattachées au guid {}", show.slug, title, guid);
+    }
+    for title in &report.unmatched {
+        eprintln!("[{}] Aucun épisode du flux ne correspond à « {} » : recommandations laissées sous ce titre", show.slug, title);
+    }
+    if !report.migrated.is_empty() {
+        store.replace_all(&map)?;
+    }
+    Ok(())
+}
+
+/// Remplace les recommandations de l'émission par le contenu d'un fichier JSON.
+fn import_json(show: &Show, store: &RecommendationsStore, file: &std::path::Path) -> Result<(), StoreError> {
+    let (map, _) = store::read_json_file(file)?;
+    store.replace_all(&map)?;
+    let count: usize = map.values().map(Vec::len).sum();
+    println!("[{}] {} recommandations importées depuis {}", show.slug, count, file.display());
+    Ok(())
+}
+
+fn export_json(show: &Show, store: &RecommendationsStore, file: &std::path::Path) -> Result<(), StoreError> {
+    let map = store.all()?;
+    store::write_json_file(file, &map)?;
+    let count: usize = map.values().map(Vec::len).sum();
+    println!("[{}] {} recommandations exportées vers {}", show.slug, count, file.display());
+    Ok(())
 }
 
-fn load_recommendations() -> RecommendationsStore {
-    let mut store: RecommendationsStore = Arc::new(Mutex::new(HashMap::new()));
-    if let Ok(json) = fs::read_to_string("recommandations.json") {
-        let map: HashMap<String, Vec<Recommendation>> = serde_json::from_str(&json).unwrap_or_default();
-        store = Arc::new(Mutex::new(map));
+/// Ouvre le stockage de chaque émission selon la configuration.
+fn open_stores(config: &Config) -> Result<Vec<RecommendationsStore>, StoreError> {
+    match config.storage {
+        StorageKind::Json => config.shows.iter()
+            .map(|show| Ok(Arc::new(JsonStore::open(&show.data_file, config.backups)?) as RecommendationsStore))
+            .collect(),
+        StorageKind::Sqlite => {
+            let conn = store::open_database(&config.database)?;
+            Ok(config.shows.iter()
+                .map(|show| Arc::new(SqliteStore::new(conn.clone(), &show.slug)) as RecommendationsStore)
+                .collect())
+        }
+    }
+}
+
+/// Arrête le programme avec un message si `result` est une erreur.
+fn or_exit<T, E: std::fmt::Display>(result: Result<T, E>, context: &str) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{} : {}", context, e);
+        std::process::exit(1);
+    })
+}
+
+/// Index de l'émission désignée en ligne de commande (par défaut, la première).
+fn show_index(config: &Config, slug: Option<&str>) -> usize {
+    match slug {
+        None => 0,
+        Some(slug) => config.shows.iter().position(|s| s.slug == slug).unwrap_or_else(|| {
+            eprintln!("Émission inconnue : {}", slug);
+            std::process::exit(1);
+        }),
     }
-    store
 }
 
 #[tokio::main]
 async fn main() {
-    let store: RecommendationsStore = load_recommendations();
+    let config = or_exit(Config::load(), "Configuration invalide");
+    let open = |config: &Config| or_exit(open_stores(config), "Ouverture du stockage impossible");
+    match config.command.clone() {
+        Command::Serve => {
+            let stores = open(&config);
+            serve(config, stores).await
+        }
+        Command::Import { show, file } => {
+            let stores = open(&config);
+            let i = show_index(&config, show.as_deref());
+            let file = file.as_ref().unwrap_or(&config.shows[i].data_file);
+            or_exit(import_json(&config.shows[i], &stores[i], file), "Import impossible");
+        }
+        Command::Export { show, file } => {
+            let stores = open(&config);
+            let i = show_index(&config, show.as_deref());
+            or_exit(export_json(&config.shows[i], &stores[i], &file), "Export impossible");
+        }
+        Command::HashPassword => print_password_hash(),
+    }
+}
+
+/// Lit un mot de passe sur l'entrée standard et affiche son hash argon2.
+fn print_password_hash() {
+    eprintln!("Mot de passe :");
+    let mut password = String::new();
+    or_exit(std::io::stdin().read_line(&mut password), "Lecture du mot de passe impossible");
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        eprintln!("Mot de passe vide");
+        std::process::exit(1);
+    }
+    println!("{}", or_exit(auth::hash_password(password), "Calcul du hash impossible"));
+}
+
+/// Rattache chaque compte à sa fiche du registre : `host` s'il est renseigné, sinon la fiche
+/// qui porte le nom du compte.
+fn link_accounts_to_hosts(accounts: &[auth::Account], hosts: &HostRegistry) -> Vec<auth::Account> {
+    accounts.iter().map(|account| {
+        let mut account = account.clone();
+        match &account.host {
+            Some(id) if hosts.get(id).is_none() => {
+                eprintln!("Compte {} : chroniqueur {} absent du registre", account.login, id);
+            }
+            Some(_) => {}
+            None => account.host = hosts.find(&account.name).map(|host| host.id),
+        }
+        if account.role == auth::Role::Chroniqueur && account.host.is_none() {
+            eprintln!("Compte {} : aucune fiche chroniqueur associée, il ne pourra modifier aucune recommandation", account.login);
+        }
+        account
+    }).collect()
+}
+
+async fn serve(config: Config, stores: Vec<RecommendationsStore>) {
+    let hosts = or_exit(HostRegistry::open(&config.hosts_file), "Ouverture du registre des chroniqueurs impossible");
+    let categories = or_exit(CategoryRegistry::open(&config.categories_file), "Ouverture des catégories impossible");
+    let positions = or_exit(PositionStore::open(&config.positions_file), "Ouverture des positions d'écoute impossible");
+    let has_siblings = config.shows.len() > 1;
+    let mut shows = Vec::new();
+    for (show, store) in config.shows.iter().zip(stores) {
+        match hosts::migrate_names_to_ids(&store, &hosts) {
+            Ok(created) => {
+                for name in created {
+                    println!("[{}] Chroniqueur « {} » ajouté au registre", show.slug, name);
+                }
+            }
+            Err(e) => eprintln!("[{}] Migration des chroniqueurs vers le registre impossible : {}", show.slug, e),
+        }
+        let feed = Arc::new(FeedCache::new(show.feed_url.clone()));
+        // Chargement initial avant d'accepter des requêtes, puis rafraîchissement en tâche de fond ;
+        // les clés par titre passent aux guid dès que le flux a pu être lu, même après le démarrage
+        if let Err(e) = feed.refresh().await {
+            eprintln!("Impossible de charger le flux de {} au démarrage : {}", show.slug, e);
+        }
+        let (migrated_show, migrated_store) = (show.clone(), store.clone());
+        feed::spawn_refresh(feed.clone(), config.feed_refresh, move |episodes| {
+            if let Err(e) = migrate_to_guids(&migrated_show, &migrated_store, episodes) {
+                eprintln!("[{}] Migration des clés vers les guid impossible : {}", migrated_show.slug, e);
+            }
+        });
+        shows.push(ShowState { show: show.clone(), feed, store, has_siblings });
+    }
+    let addr = config.bind;
+    let auth = Auth::new(link_accounts_to_hosts(&config.users, &hosts), config.secure_cookies);
+    if !auth.has_accounts() {
+        eprintln!("Aucun compte dans [[users]] : personne ne pourra modifier les recommandations");
+    }
+    let positions = Arc::new(positions);
+    positions::spawn_flush(positions.clone());
+    let extras = Arc::new(ExtrasCache::default());
+    podcast::spawn_prefetch(extras.clone(), shows.iter().map(|s| s.feed.clone()).collect());
+    let state = AppState {
+        shows: Arc::new(shows),
+        auth: Arc::new(auth),
+        hosts: Arc::new(hosts),
+        categories: Arc::new(categories),
+        search: Arc::new(SearchIndex::default()),
+        positions: positions.clone(),
+        extras,
+        submissions: Arc::new(Throttle::new(
+            config.client_ip_header.clone(), moderation::ANONYMOUS_LIMIT, moderation::ANONYMOUS_WINDOW_SECS,
+        )),
+        logins: Arc::new(Throttle::new(
+            config.client_ip_header.clone(), auth::FAILED_LOGIN_LIMIT, auth::FAILED_LOGIN_WINDOW_SECS,
+        )),
+    };
+    search::spawn_rebuild(state.clone());
     let app = Router::new()
-        .route("/", get(episodes_page))
+        .route("/", get(home_page))
+        .route("/shows/{slug}", get(show_page))
         .route("/add_reco", post(add_recommendation))
+        .route("/edit_reco", post(edit_recommendation))
         .route("/delete_reco", post(delete_recommendation))
-        .with_state(store.clone());
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
+        .merge(auth::router())
+        .merge(moderation::router())
+        .merge(hosts::router())
+        .merge(profiles::router())
+        .merge(categories::router())
+        .merge(search::router())
+        .merge(filters::router())
+        .merge(positions::router())
+        .merge(episodes::router())
+        .nest("/api/v1", api::router())
+        .with_state(state);
     println!("Serveur lancé sur http://{}", addr);
     let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+    // Les positions pas encore écrites par la tâche de fond
+    if let Err(e) = positions.flush() {
+        eprintln!("Écriture des positions d'écoute impossible : {}", e);
+    }
+}
+
+/// Ctrl-C, ou SIGTERM envoyé par le gestionnaire de services.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    println!("Arrêt du serveur");
 }
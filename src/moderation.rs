@@ -0,0 +1,141 @@
+//! Page de modération : les administrateurs approuvent ou refusent les suggestions en attente.
+//!
+//! Les visiteurs sans compte alimentent cette file sans jeton CSRF ; un [`Throttle`] limite le
+//! nombre de propositions par adresse pour qu'on ne puisse pas la noyer.
+//!
+//! [`Throttle`]: crate::throttle::Throttle
+
+use axum::extract::{Form, State};
+use axum::http::StatusCode;
+use axum::http::header::LOCATION;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use crate::auth::Admin;
+use crate::categories;
+use crate::store::Status;
+use crate::{AppState, show_not_found, store_error};
+
+const PAGE: &str = "/admin/moderation";
+
+/// Propositions sans compte acceptées par adresse pendant `ANONYMOUS_WINDOW_SECS`.
+pub const ANONYMOUS_LIMIT: usize = 5;
+pub const ANONYMOUS_WINDOW_SECS: u64 = 600;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route(PAGE, get(moderation_page).post(moderate))
+}
+
+async fn moderation_page(State(state): State<AppState>, Admin(user): Admin) -> Response {
+    let mut sections = String::new();
+    for show in state.shows.iter() {
+        let map = match show.store.all() {
+            Ok(map) => map,
+            Err(e) => return store_error(e),
+        };
+        let episodes = show.feed.episodes();
+        let mut pending: Vec<_> = map.iter()
+            .flat_map(|(episode_id, list)| list.iter().map(move |rec| (episode_id, rec)))
+            .filter(|(_, rec)| rec.status == Status::Pending)
+            .collect();
+        if pending.is_empty() {
+            continue;
+        }
+        pending.sort_by_key(|(_, rec)| rec.created_at);
+        let items: String = pending.iter().map(|(episode_id, rec)| {
+            // Un épisode disparu du flux reste identifié par son guid
+            let episode = episodes.iter()
+                .find(|ep| &ep.guid == *episode_id)
+                .map_or(episode_id.as_str(), |ep| ep.title.as_str());
+            let names: Vec<String> = rec.chroniqueurs.iter().map(|id| state.hosts.display_name(id)).collect();
+            let lien = rec.lien.as_deref().map(|l| format!(
+                " — <a href='{}' target='_blank' rel='noopener'>{}</a>",
+                htmlescape::encode_attribute(l), htmlescape::encode_minimal(l)
+            )).unwrap_or_default();
+            format!(
+                "<li style='background:#fff;border-radius:14px;box-shadow:0 2px 8px #0001;padding:1em 1.2em;margin-bottom:1em;list-style:none;'>\
+                    <div style='color:#888;font-size:0.9em;'>{episode} · proposée le {date}</div>\
+                    <div style='margin:0.3em 0;'><b>{titre}</b> {type_media}{lien}</div>\
+                    <div style='color:#9147ff;font-style:italic;'>{chroniqueurs}</div>\
+                    <div style='margin:0.5em 0;'>{description}</div>\
+                    <form method='post' action='{page}' style='display:flex;gap:0.7em;'>\
+                        <input type='hidden' name='show' value='{slug}'>\
+                        <input type='hidden' name='id' value='{id}'>\
+                        <input type='hidden' name='csrf_token' value='{csrf}'>\
+                        <button name='decision' value='approve' style='background:#1db954;color:#fff;border:none;border-radius:2em;padding:0.4em 1.4em;cursor:pointer;'>Approuver</button>\
+                        <button name='decision' value='reject' style='background:#c00;color:#fff;border:none;border-radius:2em;padding:0.4em 1.4em;cursor:pointer;'>Refuser</button>\
+                    </form>\
+                </li>",
+                episode = htmlescape::encode_minimal(episode),
+                date = rec.created_at.format("%d/%m/%Y %H:%M"),
+                titre = htmlescape::encode_minimal(&rec.titre),
+                type_media = categories::badge(&state.categories.display(&rec.type_media)),
+                lien = lien,
+                chroniqueurs = crate::chroniqueurs_text(&names.iter().map(|n| htmlescape::encode_minimal(n)).collect::<Vec<_>>()),
+                description = htmlescape::encode_minimal(&rec.description),
+                page = PAGE,
+                slug = show.show.slug,
+                id = rec.id,
+                csrf = user.csrf_token,
+            )
+        }).collect();
+        sections.push_str(&format!(
+            "<h2>{} ({})</h2><ul style='padding:0;'>{}</ul>",
+            htmlescape::encode_minimal(&show.show.name), pending.len(), items
+        ));
+    }
+    if sections.is_empty() {
+        sections = "<p>Aucune suggestion en attente.</p>".to_string();
+    }
+    Html(format!(
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Modération</title>
+        </head>
+        <body style="font-family:sans-serif;margin:0;background:#f3f6fa;">
+            <main style="max-width:760px;margin:0 auto;padding:2em 1em;">
+                <a href="/">&larr; Retour au site</a>
+                <h1>Suggestions en attente</h1>
+                {sections}
+            </main>
+        </body>
+        </html>"#,
+        sections = sections
+    )).into_response()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Decision {
+    Approve,
+    Reject,
+}
+
+#[derive(Deserialize)]
+struct ModerationForm {
+    show: String,
+    id: uuid::Uuid,
+    csrf_token: String,
+    decision: Decision,
+}
+
+async fn moderate(State(state): State<AppState>, Admin(user): Admin, Form(form): Form<ModerationForm>) -> Response {
+    if let Err(e) = user.check_csrf(&form.csrf_token) {
+        return e.into_response();
+    }
+    let Some(show) = state.show(Some(&form.show)) else {
+        return show_not_found();
+    };
+    let status = match form.decision {
+        Decision::Approve => Status::Approved,
+        Decision::Reject => Status::Rejected,
+    };
+    match show.store.set_status(form.id, status) {
+        Ok(()) => (StatusCode::SEE_OTHER, [(LOCATION, PAGE)]).into_response(),
+        Err(e) => store_error(e),
+    }
+}
@@ -0,0 +1,412 @@
+//! Espace de noms Podcasting 2.0 : chapitres (`podcast:chapters`) et transcriptions
+//! (`podcast:transcript`) référencés par le flux.
+//!
+//! Une tâche de fond télécharge les fichiers des épisodes du flux et les garde en mémoire : les
+//! pages ne lisent que ce cache et n'attendent jamais l'hébergeur. Un échec est retenté plus tard.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use crate::feed::{Episode, FeedCache, TranscriptLink};
+use crate::store::is_web_url;
+
+/// Délai maximal de téléchargement d'un fichier de chapitres ou de transcription.
+const FETCH_TIMEOUT_SECS: u64 = 10;
+
+/// Intervalle entre deux passages de la tâche de fond sur les épisodes des flux.
+const PREFETCH_PASS_SECS: u64 = 60;
+
+/// Délai avant de retenter un fichier dont le téléchargement a échoué.
+const RETRY_FAILED_SECS: u64 = 600;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Chapter {
+    /// Début, en secondes.
+    pub start: f64,
+    pub title: String,
+    pub url: Option<String>,
+}
+
+/// Réplique d'une transcription.
+#[derive(Debug, Clone, Serialize)]
+pub struct Cue {
+    /// Début, en secondes.
+    pub start: f64,
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub enum ExtraError {
+    Network(reqwest::Error),
+    Status(StatusCode),
+    /// Fichier reçu mais illisible ou d'un format non pris en charge.
+    Format(String),
+}
+
+impl fmt::Display for ExtraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtraError::Network(e) => write!(f, "fichier injoignable : {}", e),
+            ExtraError::Status(status) => write!(f, "le serveur a répondu {}", status),
+            ExtraError::Format(msg) => write!(f, "fichier invalide : {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ExtraError {}
+
+impl From<reqwest::Error> for ExtraError {
+    fn from(e: reqwest::Error) -> Self {
+        ExtraError::Network(e)
+    }
+}
+
+/// Format d'une transcription, d'après son type MIME.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TranscriptFormat {
+    Json,
+    Vtt,
+    Srt,
+}
+
+impl TranscriptFormat {
+    fn from_mime(mime_type: &str) -> Option<TranscriptFormat> {
+        match mime_type {
+            "application/json" => Some(TranscriptFormat::Json),
+            "text/vtt" => Some(TranscriptFormat::Vtt),
+            "application/x-subrip" | "application/srt" | "text/srt" => Some(TranscriptFormat::Srt),
+            _ => None,
+        }
+    }
+}
+
+/// Transcription à afficher : la première dans le format le plus précis (JSON, puis VTT, puis SRT).
+pub fn preferred_transcript(links: &[TranscriptLink]) -> Option<&TranscriptLink> {
+    [TranscriptFormat::Json, TranscriptFormat::Vtt, TranscriptFormat::Srt]
+        .into_iter()
+        .find_map(|format| links.iter().find(|l| TranscriptFormat::from_mime(&l.mime_type) == Some(format)))
+}
+
+/// Chapitres et transcriptions déjà téléchargés, par URL.
+pub struct ExtrasCache {
+    client: reqwest::Client,
+    chapters: Mutex<HashMap<String, Arc<Vec<Chapter>>>>,
+    transcripts: Mutex<HashMap<String, Arc<Vec<Cue>>>>,
+    /// Moment du dernier échec, par URL.
+    failed: Mutex<HashMap<String, Instant>>,
+}
+
+impl Default for ExtrasCache {
+    fn default() -> Self {
+        ExtrasCache {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+                .build()
+                .expect("client HTTP"),
+            chapters: Mutex::default(),
+            transcripts: Mutex::default(),
+            failed: Mutex::default(),
+        }
+    }
+}
+
+impl ExtrasCache {
+    /// Chapitres déjà téléchargés (`None` tant que la tâche de fond ne les a pas obtenus).
+    pub fn chapters(&self, url: &str) -> Option<Arc<Vec<Chapter>>> {
+        self.chapters.lock().unwrap().get(url).cloned()
+    }
+
+    /// Transcription déjà téléchargée (`None` tant que la tâche de fond ne l'a pas obtenue).
+    pub fn transcript(&self, link: &TranscriptLink) -> Option<Arc<Vec<Cue>>> {
+        self.transcripts.lock().unwrap().get(&link.url).cloned()
+    }
+
+    /// Télécharge ce qui manque pour ces épisodes, un fichier après l'autre.
+    async fn prefetch(&self, episodes: &[Episode]) {
+        for ep in episodes {
+            if let Some(url) = &ep.chapters_url
+                && self.chapters(url).is_none()
+                && self.should_try(url)
+            {
+                match self.fetch_chapters(url).await {
+                    Ok(chapters) => {
+                        self.chapters.lock().unwrap().insert(url.clone(), Arc::new(chapters));
+                    }
+                    Err(e) => self.record_failure(url, &format!("Chapitres de « {} » illisibles : {}", ep.title, e)),
+                }
+            }
+            if let Some(link) = preferred_transcript(&ep.transcripts)
+                && self.transcript(link).is_none()
+                && self.should_try(&link.url)
+            {
+                match self.fetch_transcript(link).await {
+                    Ok(cues) => {
+                        self.transcripts.lock().unwrap().insert(link.url.clone(), Arc::new(cues));
+                    }
+                    Err(e) => self.record_failure(&link.url, &format!("Transcription de « {} » illisible : {}", ep.title, e)),
+                }
+            }
+        }
+    }
+
+    fn should_try(&self, url: &str) -> bool {
+        self.failed.lock().unwrap().get(url)
+            .is_none_or(|at| at.elapsed() >= Duration::from_secs(RETRY_FAILED_SECS))
+    }
+
+    fn record_failure(&self, url: &str, message: &str) {
+        eprintln!("{}", message);
+        self.failed.lock().unwrap().insert(url.to_string(), Instant::now());
+    }
+
+    async fn fetch_chapters(&self, url: &str) -> Result<Vec<Chapter>, ExtraError> {
+        parse_chapters(&self.download(url).await?)
+    }
+
+    async fn fetch_transcript(&self, link: &TranscriptLink) -> Result<Vec<Cue>, ExtraError> {
+        let format = TranscriptFormat::from_mime(&link.mime_type)
+            .ok_or_else(|| ExtraError::Format(format!("type {} non pris en charge", link.mime_type)))?;
+        let text = self.download(&link.url).await?;
+        Ok(match format {
+            TranscriptFormat::Json => parse_json_transcript(&text)?,
+            TranscriptFormat::Vtt | TranscriptFormat::Srt => parse_cues(&text),
+        })
+    }
+
+    async fn download(&self, url: &str) -> Result<String, ExtraError> {
+        let response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(ExtraError::Status(response.status()));
+        }
+        Ok(response.text().await?)
+    }
+}
+
+/// Lance la tâche de fond qui télécharge chapitres et transcriptions des épisodes des flux,
+/// y compris ceux des épisodes apparus depuis le passage précédent.
+pub fn spawn_prefetch(extras: Arc<ExtrasCache>, feeds: Vec<Arc<FeedCache>>) {
+    tokio::spawn(async move {
+        loop {
+            for feed in &feeds {
+                extras.prefetch(&feed.episodes()).await;
+            }
+            tokio::time::sleep(Duration::from_secs(PREFETCH_PASS_SECS)).await;
+        }
+    });
+}
+
+#[derive(Deserialize)]
+struct ChaptersFile {
+    chapters: Vec<ChapterEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChapterEntry {
+    start_time: f64,
+    #[serde(default)]
+    title: String,
+    url: Option<String>,
+    /// `false` : chapitre à ne pas faire figurer dans la table des matières.
+    toc: Option<bool>,
+}
+
+fn parse_chapters(json: &str) -> Result<Vec<Chapter>, ExtraError> {
+    let file: ChaptersFile = serde_json::from_str(json).map_err(|e| ExtraError::Format(e.to_string()))?;
+    let mut chapters: Vec<Chapter> = file.chapters.into_iter()
+        .filter(|c| c.toc != Some(false))
+        .map(|c| Chapter {
+            start: c.start_time,
+            title: c.title.trim().to_string(),
+            // Fichier tiers repris dans un `href` : http(s) seulement
+            url: c.url.filter(|url| is_web_url(url)),
+        })
+        .collect();
+    chapters.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(chapters)
+}
+
+#[derive(Deserialize)]
+struct JsonTranscript {
+    segments: Vec<JsonSegment>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonSegment {
+    start_time: f64,
+    speaker: Option<String>,
+    body: String,
+}
+
+fn parse_json_transcript(json: &str) -> Result<Vec<Cue>, ExtraError> {
+    let file: JsonTranscript = serde_json::from_str(json).map_err(|e| ExtraError::Format(e.to_string()))?;
+    Ok(file.segments.into_iter()
+        .map(|s| Cue { start: s.start_time, speaker: s.speaker, text: s.body.trim().to_string() })
+        .filter(|c| !c.text.is_empty())
+        .collect())
+}
+
+/// Répliques d'un fichier SRT ou WebVTT : blocs séparés par une ligne vide, dont la ligne
+/// `début --> fin` donne le temps ; la voix WebVTT `<v Nom>` donne l'intervenant.
+fn parse_cues(text: &str) -> Vec<Cue> {
+    let text = text.replace("\r\n", "\n");
+    let mut cues = Vec::new();
+    for block in text.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(start) = lines.next().and_then(|timing| timing.split("-->").next()).and_then(parse_timecode) else {
+            continue;
+        };
+        let mut speaker = None;
+        let mut body = Vec::new();
+        for line in lines {
+            let mut line = line.trim();
+            // `<v Nom>` ou `<v.classe Nom>`
+            if let Some(rest) = line.strip_prefix("<v")
+                && rest.starts_with([' ', '.'])
+                && let Some((tag, rest)) = rest.split_once('>')
+            {
+                speaker = tag.split_once(' ').map(|(_, name)| name.trim().to_string()).filter(|name| !name.is_empty());
+                line = rest;
+            }
+            let line = strip_tags(line);
+            if !line.is_empty() {
+                body.push(line);
+            }
+        }
+        if !body.is_empty() {
+            cues.push(Cue { start, speaker, text: body.join(" ") });
+        }
+    }
+    cues
+}
+
+/// `01:02:03.456` (VTT), `01:02:03,456` (SRT) ou `02:03.456`, en secondes.
+fn parse_timecode(value: &str) -> Option<f64> {
+    let value = value.trim().replace(',', ".");
+    let mut total = 0.0;
+    for part in value.split(':') {
+        // `f64::from_str` accepterait aussi « NaN », « inf », « -5 » ou « 1e3 »
+        if !part.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return None;
+        }
+        total = total * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(total)
+}
+
+/// Retire les balises de mise en forme (`<i>`, `</v>`...) d'une ligne.
+fn strip_tags(line: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(cues: &[Cue]) -> Vec<(f64, Option<&str>, &str)> {
+        cues.iter().map(|cue| (cue.start, cue.speaker.as_deref(), cue.text.as_str())).collect()
+    }
+
+    #[test]
+    fn webvtt_cues_with_voices_and_tags() {
+        let vtt = "WEBVTT\r\n\r\nNOTE relu\r\n\r\n1\r\n00:00:01.500 --> 00:00:04.000 align:start\r\n\
+            <v Jean>Bonjour <i>à tous</i></v>\r\net bienvenue\r\n\r\n\r\n\
+            01:02.250 --> 01:05.000\r\n<v.loud Marie>Salut\r\n";
+        assert_eq!(summary(&parse_cues(vtt)), vec![
+            (1.5, Some("Jean"), "Bonjour à tous et bienvenue"),
+            (62.25, Some("Marie"), "Salut"),
+        ]);
+    }
+
+    #[test]
+    fn srt_cues() {
+        let srt = "1\n00:00:00,000 --> 00:00:02,000\nPremière réplique\n\n2\n01:00:00,500 --> 01:00:03,000\n<b>Seconde</b>\nsur deux lignes\n";
+        assert_eq!(summary(&parse_cues(srt)), vec![
+            (0.0, None, "Première réplique"),
+            (3600.5, None, "Seconde sur deux lignes"),
+        ]);
+    }
+
+    #[test]
+    fn malformed_cues_are_skipped() {
+        assert!(parse_cues("").is_empty());
+        assert!(parse_cues("WEBVTT\n\nPas de minutage ici").is_empty());
+        let text = "abc --> 00:00:02.000\nMinutage illisible\n\n\
+            00:00:03.000 --> 00:00:04.000\n<i></i>\n\n\
+            00:00:05.000 --> 00:00:06.000\n<v Jean\n\n\
+            00:00:07.000 --> 00:00:08.000\nGardée";
+        assert_eq!(summary(&parse_cues(text)), vec![(7.0, None, "Gardée")]);
+    }
+
+    #[test]
+    fn timecodes() {
+        assert_eq!(parse_timecode("01:02:03.456"), Some(3723.456));
+        assert_eq!(parse_timecode(" 01:02:03,5 "), Some(3723.5));
+        assert_eq!(parse_timecode("02:03"), Some(123.0));
+        assert_eq!(parse_timecode("42"), Some(42.0));
+        for malformed in ["", ":", "01::03", "aa:bb", "NaN", "inf", "-5", "1e3", "00:01.2.3", "00:+1"] {
+            assert_eq!(parse_timecode(malformed), None, "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn chapters_sorted_without_hidden_ones() {
+        let json = r#"{"version": "1.2.0", "chapters": [
+            {"startTime": 90, "title": " Deuxième ", "url": "https://exemple.fr"},
+            {"startTime": 45.5, "title": "Caché", "toc": false},
+            {"startTime": 0}
+        ]}"#;
+        let chapters = parse_chapters(json).unwrap();
+        let summary: Vec<(f64, &str, Option<&str>)> =
+            chapters.iter().map(|c| (c.start, c.title.as_str(), c.url.as_deref())).collect();
+        assert_eq!(summary, vec![(0.0, "", None), (90.0, "Deuxième", Some("https://exemple.fr"))]);
+    }
+
+    #[test]
+    fn chapter_links_keep_only_web_urls() {
+        let json = r#"{"chapters": [
+            {"startTime": 0, "title": "Web", "url": "http://exemple.fr"},
+            {"startTime": 1, "title": "Script", "url": "javascript:alert(document.cookie)"},
+            {"startTime": 2, "title": "Données", "url": "data:text/html,<script>alert(1)</script>"},
+            {"startTime": 3, "title": "Relatif", "url": "/admin"}
+        ]}"#;
+        let urls: Vec<Option<String>> = parse_chapters(json).unwrap().into_iter().map(|c| c.url).collect();
+        assert_eq!(urls, vec![Some("http://exemple.fr".to_string()), None, None, None]);
+    }
+
+    #[test]
+    fn malformed_chapters_are_an_error() {
+        for json in ["", "{}", "[]", r#"{"chapters": [{"title": "Sans début"}]}"#, r#"{"chapters": [{"startTime": "1:00"}]}"#] {
+            assert!(matches!(parse_chapters(json), Err(ExtraError::Format(_))), "{}", json);
+        }
+    }
+
+    #[test]
+    fn json_transcript_drops_empty_segments() {
+        let json = r#"{"segments": [
+            {"startTime": 1.0, "speaker": "Jean", "body": " Bonjour "},
+            {"startTime": 2.0, "body": "   "},
+            {"startTime": 3.0, "body": "Au revoir"}
+        ]}"#;
+        assert_eq!(summary(&parse_json_transcript(json).unwrap()), vec![
+            (1.0, Some("Jean"), "Bonjour"),
+            (3.0, None, "Au revoir"),
+        ]);
+        assert!(parse_json_transcript(r#"{"segments": [{"body": "Sans début"}]}"#).is_err());
+    }
+}
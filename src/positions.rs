@@ -0,0 +1,170 @@
+//! Positions d'écoute des comptes connectés, pour reprendre un épisode sur un autre appareil.
+//!
+//! Le lecteur garde la position de chaque épisode dans le navigateur (`localStorage`) ; pour les
+//! comptes connectés, il l'envoie aussi à `/positions/{show}`. Les positions envoyées sont
+//! gardées en mémoire et écrites dans `positions_file` au plus toutes les `FLUSH_SECS` secondes,
+//! ainsi qu'à l'arrêt du serveur : le lecteur en envoie une toutes les quelques secondes.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use axum::extract::{Form, Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::auth::CurrentUser;
+use crate::store::{self, StoreError};
+use crate::{AppState, show_not_found};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    /// Secondes écoutées depuis le début de l'épisode.
+    pub seconds: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Positions d'une émission, par guid d'épisode.
+type ShowPositions = BTreeMap<String, Position>;
+
+/// Intervalle entre deux écritures du fichier des positions.
+const FLUSH_SECS: u64 = 10;
+
+/// Positions par identifiant de compte, puis par émission.
+pub struct PositionStore {
+    path: PathBuf,
+    positions: Mutex<BTreeMap<String, BTreeMap<String, ShowPositions>>>,
+    /// Positions modifiées depuis la dernière écriture du fichier.
+    dirty: AtomicBool,
+}
+
+impl PositionStore {
+    /// Ouvre le fichier ; absent, il sera créé au premier enregistrement.
+    pub fn open(path: &Path) -> Result<PositionStore, StoreError> {
+        let positions = match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| StoreError::Corrupt(path.to_path_buf(), e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(PositionStore { path: path.to_path_buf(), positions: Mutex::new(positions), dirty: AtomicBool::new(false) })
+    }
+
+    pub fn for_show(&self, login: &str, show: &str) -> ShowPositions {
+        let positions = self.positions.lock().unwrap();
+        positions.get(login).and_then(|shows| shows.get(show)).cloned().unwrap_or_default()
+    }
+
+    /// Enregistre en mémoire ; le fichier est écrit par `flush`.
+    pub fn save(&self, login: &str, show: &str, episode_id: &str, seconds: f64) {
+        let mut positions = self.positions.lock().unwrap();
+        positions.entry(login.to_string()).or_default()
+            .entry(show.to_string()).or_default()
+            .insert(episode_id.to_string(), Position { seconds, updated_at: Utc::now() });
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Écrit le fichier si des positions ont changé depuis la dernière écriture.
+    pub fn flush(&self) -> Result<(), StoreError> {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let positions = self.positions.lock().unwrap().clone();
+        store::write_json_file(&self.path, &positions).inspect_err(|_| self.dirty.store(true, Ordering::SeqCst))
+    }
+}
+
+/// Écrit les positions modifiées toutes les `FLUSH_SECS` secondes.
+pub fn spawn_flush(positions: Arc<PositionStore>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(FLUSH_SECS)).await;
+            if let Err(e) = positions.flush() {
+                eprintln!("Écriture des positions d'écoute impossible : {}", e);
+            }
+        }
+    });
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/positions/{show}", get(list_positions).post(save_position))
+}
+
+async fn list_positions(State(state): State<AppState>, user: CurrentUser, UrlPath(slug): UrlPath<String>) -> Response {
+    if state.show(Some(&slug)).is_none() {
+        return show_not_found();
+    }
+    Json(state.positions.for_show(&user.user.login, &slug)).into_response()
+}
+
+#[derive(Deserialize)]
+struct PositionForm {
+    episode_id: String,
+    seconds: f64,
+    csrf_token: String,
+}
+
+async fn save_position(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    UrlPath(slug): UrlPath<String>,
+    Form(form): Form<PositionForm>,
+) -> Response {
+    if let Err(e) = user.check_csrf(&form.csrf_token) {
+        return e.into_response();
+    }
+    let Some(show) = state.show(Some(&slug)) else {
+        return show_not_found();
+    };
+    if !show.feed.episodes().iter().any(|ep| ep.guid == form.episode_id) {
+        return (StatusCode::NOT_FOUND, "Épisode inconnu").into_response();
+    }
+    if !form.seconds.is_finite() || form.seconds < 0.0 {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Position invalide").into_response();
+    }
+    state.positions.save(&user.user.login, &slug, &form.episode_id, form.seconds);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("pap-positions-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn saves_are_written_on_flush_only() {
+        let path = temp_path();
+        let store = PositionStore::open(&path).unwrap();
+        store.save("jean", "pap", "guid-1", 12.0);
+        store.save("jean", "pap", "guid-1", 42.5);
+        assert!(!path.exists());
+        assert_eq!(store.for_show("jean", "pap")["guid-1"].seconds, 42.5);
+
+        store.flush().unwrap();
+        let reopened = PositionStore::open(&path).unwrap();
+        assert_eq!(reopened.for_show("jean", "pap")["guid-1"].seconds, 42.5);
+        assert!(reopened.for_show("jean", "autre").is_empty());
+
+        // Rien de nouveau : pas de réécriture
+        fs::remove_file(&path).unwrap();
+        store.flush().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn failed_flush_is_retried() {
+        let path = temp_path().join("positions.json");
+        let store = PositionStore::open(&path).unwrap();
+        store.save("jean", "pap", "guid-1", 12.0);
+        assert!(store.flush().is_err());
+        assert!(store.dirty.load(Ordering::SeqCst));
+    }
+}
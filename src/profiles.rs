@@ -0,0 +1,200 @@
+//! Pages publiques des chroniqueurs : `/chroniqueurs` et `/chroniqueurs/{id}`.
+//!
+//! Le profil regroupe, toutes émissions confondues, les recommandations approuvées où figure
+//! le chroniqueur : répartition par type, choix les plus récents et épisodes auxquels il a participé.
+
+use std::collections::HashMap;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use crate::hosts::Host;
+use crate::categories;
+use crate::episodes;
+use crate::store::{Recommendation, Status};
+use crate::{AppState, ShowState, store_error};
+
+/// Nombre de recommandations affichées dans « Derniers choix ».
+const RECENT_PICKS: usize = 5;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/chroniqueurs", get(hosts_index))
+        .route("/chroniqueurs/{id}", get(profile_page))
+}
+
+fn page(title: &str, body: String) -> Response {
+    Html(format!(
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>{title}</title>
+        </head>
+        <body style="font-family:sans-serif;margin:0;background:#f3f6fa;">
+            <main style="max-width:760px;margin:0 auto;padding:2em 1em;">
+                <a href="/">&larr; Retour au site</a>
+                {body}
+            </main>
+        </body>
+        </html>"#,
+        title = htmlescape::encode_minimal(title),
+        body = body,
+    )).into_response()
+}
+
+fn avatar(host: &Host, size: u32) -> String {
+    host.avatar_url.as_deref().map(|url| format!(
+        "<img src='{}' alt='' style='width:{size}px;height:{size}px;border-radius:50%;object-fit:cover;box-shadow:0 2px 8px #0002;'>",
+        htmlescape::encode_attribute(url),
+        size = size,
+    )).unwrap_or_default()
+}
+
+async fn hosts_index(State(state): State<AppState>) -> Response {
+    let items: String = state.hosts.all().iter().map(|host| format!(
+        "<li style='display:flex;align-items:center;gap:0.8em;margin-bottom:0.8em;'>{avatar}<a href='/chroniqueurs/{id}'>{name}</a></li>",
+        avatar = avatar(host, 40),
+        id = htmlescape::encode_attribute(&host.id),
+        name = htmlescape::encode_minimal(&host.name),
+    )).collect();
+    let list = if items.is_empty() {
+        "<p>Aucun chroniqueur pour l'instant.</p>".to_string()
+    } else {
+        format!("<ul style='list-style:none;padding:0;'>{}</ul>", items)
+    };
+    page("Les chroniqueurs", format!("<h1>Les chroniqueurs</h1>{}", list))
+}
+
+/// Recommandation du chroniqueur, avec l'émission et l'épisode où elle a été faite.
+struct Pick<'a> {
+    show: &'a ShowState,
+    episode_id: String,
+    rec: Recommendation,
+}
+
+impl Pick<'_> {
+    /// Titre de l'épisode d'après le flux ; son guid s'il n'y figure plus.
+    fn episode_title(&self) -> String {
+        self.show.feed.episodes().iter()
+            .find(|ep| ep.guid == self.episode_id)
+            .map_or_else(|| self.episode_id.clone(), |ep| ep.title.clone())
+    }
+}
+
+async fn profile_page(State(state): State<AppState>, UrlPath(id): UrlPath<String>) -> Response {
+    let Some(host) = state.hosts.get(&id) else {
+        return (StatusCode::NOT_FOUND, "Chroniqueur inconnu").into_response();
+    };
+    let mut picks = Vec::new();
+    for show in state.shows.iter() {
+        let map = match show.store.all() {
+            Ok(map) => map,
+            Err(e) => return store_error(e),
+        };
+        for (episode_id, list) in map {
+            picks.extend(list.into_iter()
+                .filter(|rec| rec.status == Status::Approved && rec.chroniqueurs.contains(&host.id))
+                .map(|rec| Pick { show, episode_id: episode_id.clone(), rec }));
+        }
+    }
+    picks.sort_by_key(|pick| std::cmp::Reverse(pick.rec.created_at));
+
+    let mut by_type: HashMap<&str, usize> = HashMap::new();
+    for pick in &picks {
+        *by_type.entry(pick.rec.type_media.as_str()).or_default() += 1;
+    }
+    let mut by_type: Vec<_> = by_type.into_iter()
+        .map(|(id, count)| (state.categories.display(id), count))
+        .collect();
+    by_type.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.position.cmp(&b.0.position)));
+    let counts: String = by_type.iter().map(|(category, count)| format!(
+        "<span style='background:{};color:#fff;border-radius:50px;box-shadow:0 2px 8px #0001;padding:0.3em 1em;'>{} {} <b>{}</b></span>",
+        htmlescape::encode_attribute(&category.color),
+        htmlescape::encode_minimal(&category.icon),
+        htmlescape::encode_minimal(&category.label),
+        count
+    )).collect();
+
+    let recent: String = picks.iter().take(RECENT_PICKS).map(|pick| {
+        let titre = match &pick.rec.lien {
+            Some(lien) => format!(
+                "<a href='{}' target='_blank' rel='noopener'>{}</a>",
+                htmlescape::encode_attribute(lien), htmlescape::encode_minimal(&pick.rec.titre)
+            ),
+            None => htmlescape::encode_minimal(&pick.rec.titre),
+        };
+        format!(
+            "<li style='background:#fff;border-radius:14px;box-shadow:0 2px 8px #0001;padding:0.9em 1.2em;margin-bottom:0.8em;list-style:none;'>\
+                <div><b>{titre}</b> {type_media}</div>\
+                <div style='margin:0.3em 0;'>{description}</div>\
+                <div style='color:#888;font-size:0.9em;'>{episode} · {date}</div>\
+            </li>",
+            titre = titre,
+            type_media = categories::badge(&state.categories.display(&pick.rec.type_media)),
+            description = htmlescape::encode_minimal(&pick.rec.description),
+            episode = htmlescape::encode_minimal(&pick.episode_title()),
+            date = pick.rec.created_at.format("%d/%m/%Y"),
+        )
+    }).collect();
+
+    // Épisodes dans l'ordre du flux de chaque émission, chacun avec son nombre de recommandations
+    let mut episodes = String::new();
+    for show in state.shows.iter() {
+        let count_for = |guid: &str| picks.iter()
+            .filter(|p| std::ptr::eq(p.show, show) && p.episode_id == guid)
+            .count();
+        let feed = show.feed.episodes();
+        let items: String = feed.iter()
+            .map(|ep| (ep, count_for(&ep.guid)))
+            .filter(|(_, count)| *count > 0)
+            .map(|(ep, count)| format!(
+                "<li><a href='{path}'>{title}</a> <span style='color:#888;'>({date}, {count} recommandation{s})</span></li>",
+                path = htmlescape::encode_attribute(&episodes::episode_path(show, ep)),
+                title = htmlescape::encode_minimal(&ep.title),
+                date = htmlescape::encode_minimal(&ep.date),
+                count = count,
+                s = if count > 1 { "s" } else { "" },
+            ))
+            .collect();
+        if !items.is_empty() {
+            let heading = if state.shows.len() > 1 {
+                format!("<h3>{}</h3>", htmlescape::encode_minimal(&show.show.name))
+            } else {
+                String::new()
+            };
+            episodes.push_str(&format!("{}<ul>{}</ul>", heading, items));
+        }
+    }
+
+    let bio = host.bio.as_deref().map(|bio| format!("<p>{}</p>", htmlescape::encode_minimal(bio))).unwrap_or_default();
+    let links: String = host.links.iter().map(|link| format!(
+        "<a href='{}' target='_blank' rel='noopener' style='margin-right:1em;'>{}</a>",
+        htmlescape::encode_attribute(link), htmlescape::encode_minimal(link)
+    )).collect();
+    let body = if picks.is_empty() {
+        "<p>Aucune recommandation pour l'instant.</p>".to_string()
+    } else {
+        format!(
+            "<h2>{total} recommandation{s}</h2>\
+            <div style='display:flex;flex-wrap:wrap;gap:0.6em;'>{counts}</div>\
+            <h2>Derniers choix</h2><ul style='padding:0;'>{recent}</ul>\
+            <h2>Épisodes</h2>{episodes}",
+            total = picks.len(),
+            s = if picks.len() > 1 { "s" } else { "" },
+            counts = counts,
+            recent = recent,
+            episodes = episodes,
+        )
+    };
+    page(&host.name, format!(
+        "<div style='display:flex;align-items:center;gap:1em;margin-top:1em;'>{avatar}<h1>{name}</h1></div>{bio}<div>{links}</div>{body}",
+        avatar = avatar(&host, 88),
+        name = htmlescape::encode_minimal(&host.name),
+        bio = bio,
+        links = links,
+        body = body,
+    ))
+}
@@ -0,0 +1,544 @@
+//! Recherche plein texte dans les épisodes et les recommandations, toutes émissions confondues.
+//!
+//! L'index est gardé en mémoire et reconstruit par une tâche de fond dans les secondes qui suivent
+//! un changement (nouveau contenu du flux, écriture dans un stockage ou modification du registre
+//! des chroniqueurs) : une recherche utilise l'index courant sans attendre sa reconstruction.
+//! Les mots sont comparés sans casse ni accents, et un mot de la requête trouve les mots qui
+//! commencent par lui (« livr » trouve « livres »). Tous les mots de la requête doivent figurer
+//! dans le résultat.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use axum::extract::{Query, State};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use crate::categories;
+use crate::episodes;
+use crate::feed::Episode;
+use crate::hosts::fold_accents;
+use crate::store::{Recommendation, Status, StoreError};
+use crate::{AppState, store_error};
+
+/// Intervalle entre deux vérifications de l'index par la tâche de fond.
+const REBUILD_CHECK_SECS: u64 = 2;
+/// Nombre maximal de résultats renvoyés.
+const MAX_RESULTS: usize = 50;
+/// Longueur des extraits de description, en mots.
+const EXCERPT_WORDS: usize = 30;
+/// Mots trop courants pour départager les résultats.
+const STOP_WORDS: &[&str] = &[
+    "a", "au", "aux", "d", "de", "des", "du", "en", "et", "l", "la", "le", "les", "un", "une",
+];
+
+// Poids des champs dans le score
+const TITLE: u32 = 3;
+const HOSTS: u32 = 2;
+const TEXT: u32 = 1;
+
+/// Résultat de recherche, tel que renvoyé par l'API JSON.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Hit {
+    Episode {
+        show: String,
+        episode_id: String,
+        title: String,
+        date: String,
+        /// Description en texte brut, réduite à un extrait autour des mots trouvés.
+        excerpt: String,
+    },
+    Recommendation {
+        show: String,
+        episode_id: String,
+        episode_title: String,
+        /// Noms affichés des chroniqueurs.
+        chroniqueurs: Vec<String>,
+        recommendation: Recommendation,
+    },
+}
+
+struct Document {
+    hit: Hit,
+    /// Mots repliés de chaque champ, avec le poids du champ.
+    fields: Vec<(u32, Vec<String>)>,
+}
+
+struct Index {
+    /// Contenu des flux et révisions des stockages (dans l'ordre des émissions) au moment de la construction.
+    feeds: Vec<Arc<Vec<Episode>>>,
+    revisions: Vec<u64>,
+    hosts_revision: u64,
+    documents: Vec<Document>,
+    /// Mot replié → documents qui le contiennent.
+    postings: BTreeMap<String, Vec<usize>>,
+}
+
+/// Mots d'un texte, dans leur graphie d'origine.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty())
+}
+
+fn fold(word: &str) -> String {
+    fold_accents(&word.to_lowercase())
+}
+
+fn folded_words(text: &str) -> Vec<String> {
+    words(text).map(fold).collect()
+}
+
+/// Mots de la requête, repliés, sans doublons ni mots vides.
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in words(query).map(fold) {
+        if !STOP_WORDS.contains(&term.as_str()) && !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+fn matches(word: &str, terms: &[String]) -> bool {
+    let word = fold(word);
+    terms.iter().any(|term| word.starts_with(term.as_str()))
+}
+
+/// Texte brut d'une description HTML du flux.
+fn plain_text(html: &str) -> String {
+    let html = html.replace("<![CDATA[", "").replace("]]>", "");
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Extrait de `text` autour du premier mot trouvé ; le début du texte si aucun ne l'est.
+fn excerpt(text: &str, terms: &[String]) -> String {
+    let all: Vec<&str> = text.split_whitespace().collect();
+    let first = all.iter().position(|w| words(w).any(|w| matches(w, terms))).unwrap_or(0);
+    let start = first.saturating_sub(EXCERPT_WORDS / 3);
+    let end = (start + EXCERPT_WORDS).min(all.len());
+    let mut excerpt = all[start..end].join(" ");
+    if start > 0 {
+        excerpt.insert_str(0, "… ");
+    }
+    if end < all.len() {
+        excerpt.push_str(" …");
+    }
+    excerpt
+}
+
+/// Texte échappé pour le HTML, les mots trouvés entourés de `<mark>`.
+pub fn highlight(text: &str, terms: &[String]) -> String {
+    let mut html = String::with_capacity(text.len());
+    let mut start = 0;
+    let mut in_word = false;
+    // Découpe en suites alternées de caractères alphanumériques et d'autres caractères
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        if c.is_alphanumeric() == in_word && i < text.len() {
+            continue;
+        }
+        let part = &text[start..i];
+        if in_word && matches(part, terms) {
+            html.push_str(&format!("<mark>{}</mark>", htmlescape::encode_minimal(part)));
+        } else {
+            html.push_str(&htmlescape::encode_minimal(part));
+        }
+        start = i;
+        in_word = c.is_alphanumeric();
+    }
+    html
+}
+
+/// Mot replié → documents qui le contiennent, chacun une seule fois et dans l'ordre.
+fn postings(documents: &[Document]) -> BTreeMap<String, Vec<usize>> {
+    let mut postings: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, document) in documents.iter().enumerate() {
+        let unique: HashSet<&String> = document.fields.iter().flat_map(|(_, words)| words).collect();
+        for word in unique {
+            postings.entry(word.clone()).or_default().push(i);
+        }
+    }
+    postings
+}
+
+impl Index {
+    /// Vrai si ni les flux, ni les stockages, ni le registre n'ont changé depuis la construction.
+    fn is_fresh(&self, state: &AppState) -> bool {
+        self.hosts_revision == state.hosts.revision()
+            && state.shows.iter().enumerate().all(|(i, show)| {
+                Arc::ptr_eq(&self.feeds[i], &show.feed.episodes()) && self.revisions[i] == show.store.revision()
+            })
+    }
+
+    fn build(state: &AppState) -> Result<Index, StoreError> {
+        // Révisions lues avant les données : une écriture concurrente provoque au pire une reconstruction de trop
+        let hosts_revision = state.hosts.revision();
+        let revisions: Vec<u64> = state.shows.iter().map(|show| show.store.revision()).collect();
+        let feeds: Vec<_> = state.shows.iter().map(|show| show.feed.episodes()).collect();
+        let mut documents = Vec::new();
+        for (show, episodes) in state.shows.iter().zip(&feeds) {
+            let map = show.store.all()?;
+            let slug = &show.show.slug;
+            for episode in episodes.iter() {
+                let description = plain_text(&episode.description);
+                documents.push(Document {
+                    fields: vec![(TITLE, folded_words(&episode.title)), (TEXT, folded_words(&description))],
+                    hit: Hit::Episode {
+                        show: slug.clone(),
+                        episode_id: episode.guid.clone(),
+                        title: episode.title.clone(),
+                        date: episode.date.clone(),
+                        excerpt: description,
+                    },
+                });
+                let recs = map.get(&episode.guid).into_iter().flatten().filter(|rec| rec.status == Status::Approved);
+                for rec in recs {
+                    let hosts: Vec<_> = rec.chroniqueurs.iter().filter_map(|id| state.hosts.get(id)).collect();
+                    let mut host_words = Vec::new();
+                    for host in &hosts {
+                        host_words.extend(folded_words(&host.name));
+                        host_words.extend(host.aliases.iter().flat_map(|alias| folded_words(alias)));
+                    }
+                    documents.push(Document {
+                        fields: vec![
+                            (TITLE, folded_words(&rec.titre)),
+                            (HOSTS, host_words),
+                            (TEXT, folded_words(&rec.description)),
+                        ],
+                        hit: Hit::Recommendation {
+                            show: slug.clone(),
+                            episode_id: episode.guid.clone(),
+                            episode_title: episode.title.clone(),
+                            chroniqueurs: hosts.into_iter().map(|host| host.name).collect(),
+                            recommendation: rec.clone(),
+                        },
+                    });
+                }
+            }
+        }
+        let postings = postings(&documents);
+        Ok(Index { feeds, revisions, hosts_revision, documents, postings })
+    }
+
+    /// Documents contenant un mot qui commence par `term`.
+    fn documents_with(&self, term: &str) -> HashSet<usize> {
+        self.postings
+            .range(term.to_string()..)
+            .take_while(|(word, _)| word.starts_with(term))
+            .flat_map(|(_, documents)| documents.iter().copied())
+            .collect()
+    }
+
+    fn search(&self, terms: &[String]) -> Vec<Hit> {
+        let mut found: Option<HashSet<usize>> = None;
+        for term in terms {
+            let with_term = self.documents_with(term);
+            found = Some(match found {
+                None => with_term,
+                Some(found) => found.intersection(&with_term).copied().collect(),
+            });
+        }
+        let mut scored: Vec<(u32, usize)> = found.unwrap_or_default().into_iter()
+            .map(|i| {
+                let fields = &self.documents[i].fields;
+                let score = terms.iter()
+                    .map(|term| fields.iter()
+                        .filter(|(_, words)| words.iter().any(|w| w.starts_with(term.as_str())))
+                        .map(|(weight, _)| *weight)
+                        .max()
+                        .unwrap_or(0))
+                    .sum();
+                (score, i)
+            })
+            .collect();
+        // Meilleur score d'abord, puis dans l'ordre des flux (épisodes récents en tête)
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored.into_iter().take(MAX_RESULTS).map(|(_, i)| {
+            let mut hit = self.documents[i].hit.clone();
+            if let Hit::Episode { excerpt: text, .. } = &mut hit {
+                *text = excerpt(text, terms);
+            }
+            hit
+        }).collect()
+    }
+}
+
+/// Index de recherche partagé, tenu à jour par [`spawn_rebuild`].
+#[derive(Default)]
+pub struct SearchIndex {
+    index: Mutex<Option<Arc<Index>>>,
+}
+
+impl SearchIndex {
+    /// Résultats pour `query`, par pertinence ; aucun si la requête ne contient que des mots vides.
+    pub fn search(&self, state: &AppState, query: &str) -> Result<Vec<Hit>, StoreError> {
+        let terms = query_terms(query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        // L'index courant, même en retard d'un changement ; construit ici seulement s'il n'y en a pas encore
+        let current = self.index.lock().unwrap().clone();
+        let index = match current {
+            Some(index) => index,
+            None => self.rebuild_if_stale(state)?,
+        };
+        Ok(index.search(&terms))
+    }
+
+    /// Reconstruit l'index s'il ne correspond plus au contenu, sans bloquer les recherches en cours.
+    fn rebuild_if_stale(&self, state: &AppState) -> Result<Arc<Index>, StoreError> {
+        let current = self.index.lock().unwrap().clone();
+        if let Some(index) = current && index.is_fresh(state) {
+            return Ok(index);
+        }
+        let built = Arc::new(Index::build(state)?);
+        *self.index.lock().unwrap() = Some(built.clone());
+        Ok(built)
+    }
+}
+
+/// Lance la tâche de fond qui reconstruit l'index après chaque changement.
+pub fn spawn_rebuild(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            let rebuild_state = state.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                rebuild_state.search.rebuild_if_stale(&rebuild_state).map(|_| ())
+            }).await;
+            if let Ok(Err(e)) = result {
+                eprintln!("Construction de l'index de recherche impossible : {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(REBUILD_CHECK_SECS)).await;
+        }
+    });
+}
+
+// Page de recherche
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/search", get(search_page))
+}
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub q: String,
+}
+
+fn render_hit(state: &AppState, hit: &Hit, terms: &[String]) -> String {
+    let (show_slug, episode_id, episode_title) = match hit {
+        Hit::Episode { show, episode_id, title, .. } => (show, episode_id, title),
+        Hit::Recommendation { show, episode_id, episode_title, .. } => (show, episode_id, episode_title),
+    };
+    let Some(show) = state.show(Some(show_slug)) else {
+        return String::new();
+    };
+    // L'index peut avoir été construit avant un rafraîchissement du flux
+    let path = match show.feed.episodes().iter().find(|ep| &ep.guid == episode_id) {
+        Some(ep) => episodes::episode_path(show, ep),
+        None => show.page_path(),
+    };
+    let show_name = if state.shows.len() > 1 {
+        format!("{} · ", htmlescape::encode_minimal(&show.show.name))
+    } else {
+        String::new()
+    };
+    let body = match hit {
+        Hit::Episode { date, excerpt, .. } => format!(
+            "<div style='color:#888;font-size:0.9em;'>{show}Épisode du {date}</div>\
+            <div style='font-weight:bold;margin:0.2em 0;'><a href='{path}'>{title}</a></div>\
+            <div>{excerpt}</div>",
+            show = show_name,
+            date = htmlescape::encode_minimal(date),
+            path = htmlescape::encode_attribute(&path),
+            title = highlight(episode_title, terms),
+            excerpt = highlight(excerpt, terms),
+        ),
+        Hit::Recommendation { chroniqueurs, recommendation: rec, .. } => format!(
+            "<div style='color:#888;font-size:0.9em;'>{show}Recommandation · <a href='{path}'>{episode}</a></div>\
+            <div style='margin:0.3em 0;'><b>{titre}</b> {badge}</div>\
+            <div style='color:#9147ff;font-style:italic;'>{chroniqueurs}</div>\
+            <div>{description}</div>",
+            show = show_name,
+            path = htmlescape::encode_attribute(&path),
+            episode = htmlescape::encode_minimal(episode_title),
+            titre = highlight(&rec.titre, terms),
+            badge = categories::badge(&state.categories.display(&rec.type_media)),
+            chroniqueurs = highlight(&chroniqueurs.join(", "), terms),
+            description = highlight(&rec.description, terms),
+        ),
+    };
+    format!(
+        "<li style='background:#fff;border-radius:14px;box-shadow:0 2px 8px #0001;padding:1em 1.2em;margin-bottom:1em;list-style:none;'>{}</li>",
+        body
+    )
+}
+
+async fn search_page(State(state): State<AppState>, Query(query): Query<SearchQuery>) -> Response {
+    let query = query.q.trim();
+    let results = if query.is_empty() {
+        String::new()
+    } else {
+        let hits = match state.search.search(&state, query) {
+            Ok(hits) => hits,
+            Err(e) => return store_error(e),
+        };
+        let terms = query_terms(query);
+        if hits.is_empty() {
+            format!("<p>Aucun résultat pour « {} ».</p>", htmlescape::encode_minimal(query))
+        } else {
+            let items: String = hits.iter().map(|hit| render_hit(&state, hit, &terms)).collect();
+            format!("<ul style='padding:0;'>{}</ul>", items)
+        }
+    };
+    Html(format!(
+        r#"<!DOCTYPE html>
+        <html lang="fr">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Recherche</title>
+            <style>mark {{ background:#ffe082; padding:0 0.1em; border-radius:3px; }}</style>
+        </head>
+        <body style="font-family:sans-serif;margin:0;background:#f3f6fa;">
+            <main style="max-width:760px;margin:0 auto;padding:2em 1em;">
+                <a href="/">&larr; Retour au site</a>
+                <h1>Recherche</h1>
+                <form method="get" action="/search" style="display:flex;gap:0.5em;margin-bottom:1.5em;">
+                    <input type="search" name="q" value="{query}" placeholder="Un titre, un chroniqueur, un mot de la description..." autofocus style="flex:1;padding:0.6em;border-radius:8px;border:1px solid #ccc;font-size:1em;">
+                    <button type="submit" style="background:linear-gradient(90deg,#ffb347,#ffcc33);font-weight:bold;border:none;border-radius:2em;padding:0.6em 1.6em;cursor:pointer;">Chercher</button>
+                </form>
+                {results}
+            </main>
+        </body>
+        </html>"#,
+        query = htmlescape::encode_attribute(query),
+        results = results,
+    )).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::RecommendationInput;
+
+    fn document(title: &str, text: &str) -> Document {
+        Document {
+            fields: vec![(TITLE, folded_words(title)), (TEXT, folded_words(text))],
+            hit: Hit::Episode {
+                show: "pap".to_string(),
+                episode_id: title.to_string(),
+                title: title.to_string(),
+                date: String::new(),
+                excerpt: text.to_string(),
+            },
+        }
+    }
+
+    fn index(documents: Vec<Document>) -> Index {
+        let postings = postings(&documents);
+        Index { feeds: Vec::new(), revisions: Vec::new(), hosts_revision: 0, documents, postings }
+    }
+
+    fn titles(hits: &[Hit]) -> Vec<&str> {
+        hits.iter().map(|hit| match hit {
+            Hit::Episode { title, .. } => title.as_str(),
+            Hit::Recommendation { recommendation, .. } => recommendation.titre.as_str(),
+        }).collect()
+    }
+
+    #[test]
+    fn query_terms_are_folded_without_stop_words_or_duplicates() {
+        assert_eq!(query_terms("L'Été des CHÂTEAUX, été"), vec!["ete", "chateaux"]);
+        assert_eq!(query_terms("Œuvre naïve"), vec!["oeuvre", "naive"]);
+        assert!(query_terms("de la ... !").is_empty());
+    }
+
+    #[test]
+    fn search_ignores_accents_and_matches_prefixes() {
+        let index = index(vec![document("Éléphants d'Afrique", "Un reportage"), document("Cinéma", "Des élections")]);
+        assert_eq!(titles(&index.search(&query_terms("elephant"))), vec!["Éléphants d'Afrique"]);
+        assert_eq!(titles(&index.search(&query_terms("ÉLE"))), vec!["Éléphants d'Afrique", "Cinéma"]);
+        assert!(index.search(&query_terms("phant")).is_empty());
+    }
+
+    #[test]
+    fn every_term_must_match_and_titles_rank_first() {
+        let index = index(vec![
+            document("Livres de vacances", "Des romans pour l'été"),
+            document("Films", "Des livres et des films"),
+            document("Romans", "Livres de poche"),
+        ]);
+        assert_eq!(titles(&index.search(&query_terms("livre roman"))), vec!["Livres de vacances", "Romans"]);
+        assert_eq!(titles(&index.search(&query_terms("livres"))), vec!["Livres de vacances", "Films", "Romans"]);
+        assert!(index.search(&query_terms("livre inconnu")).is_empty());
+    }
+
+    #[test]
+    fn recommendations_are_found_by_host_words() {
+        let rec = RecommendationInput {
+            chroniqueurs: vec!["jean".to_string()],
+            titre: "Dune".to_string(),
+            lien: None,
+            type_media: "livre".to_string(),
+            description: "Du sable".to_string(),
+            timestamp: None,
+        }.into_recommendation(Status::Approved);
+        let index = index(vec![Document {
+            fields: vec![(TITLE, folded_words("Dune")), (HOSTS, folded_words("Jérôme")), (TEXT, folded_words("Du sable"))],
+            hit: Hit::Recommendation {
+                show: "pap".to_string(),
+                episode_id: "guid".to_string(),
+                episode_title: "Épisode".to_string(),
+                chroniqueurs: vec!["Jérôme".to_string()],
+                recommendation: rec,
+            },
+        }]);
+        assert_eq!(titles(&index.search(&query_terms("jerome sable"))), vec!["Dune"]);
+    }
+
+    #[test]
+    fn highlight_marks_words_on_multibyte_text() {
+        let terms = query_terms("ete noel");
+        assert_eq!(highlight("L'été à Noël, <b>étés</b>", &terms),
+            "L&#x27;<mark>été</mark> à <mark>Noël</mark>, &lt;b&gt;<mark>étés</mark>&lt;/b&gt;");
+        assert_eq!(highlight("Œil pour œil", &query_terms("oeil")), "<mark>Œil</mark> pour <mark>œil</mark>");
+        assert_eq!(highlight("", &terms), "");
+        assert_eq!(highlight("très", &query_terms("z")), "très");
+    }
+
+    #[test]
+    fn excerpt_centers_on_the_first_match() {
+        let text: Vec<String> = (0..100).map(|i| if i == 50 { "Érable".to_string() } else { format!("mot{}", i) }).collect();
+        let text = text.join(" ");
+        let found = excerpt(&text, &query_terms("erable"));
+        assert!(found.starts_with("… mot40 ") && found.ends_with(" …"), "{}", found);
+        assert!(found.contains("Érable"));
+        assert_eq!(found.split_whitespace().filter(|w| *w != "…").count(), EXCERPT_WORDS);
+
+        let start = excerpt(&text, &query_terms("absent"));
+        assert!(start.starts_with("mot0 ") && start.ends_with(" …"));
+        assert_eq!(excerpt("Court été", &query_terms("ete")), "Court été");
+        assert_eq!(excerpt("", &query_terms("ete")), "");
+    }
+}
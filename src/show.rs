@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+use serde::Deserialize;
+
+const PAP_COVER_URL: &str = "https://assets.pippa.io/shows/cover/1678196289243-eb4dc05a818625489cad37a30940fd3b.jpeg";
+
+/// Un podcast servi par le site, avec son flux, ses liens d'écoute et ses recommandations.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Show {
+    /// Identifiant utilisé dans les URL (`/shows/{slug}`).
+    pub slug: String,
+    pub name: String,
+    pub feed_url: String,
+    /// Fichier des recommandations de l'émission (stockage JSON, et fichier d'import par défaut).
+    /// Avec SQLite, le slug sert d'espace de noms dans la base commune.
+    pub data_file: PathBuf,
+    #[serde(default)]
+    pub links: PlatformLinks,
+    #[serde(default)]
+    pub branding: Branding,
+}
+
+/// Liens vers les plateformes d'écoute ; seuls ceux renseignés sont affichés.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlatformLinks {
+    pub apple: Option<String>,
+    pub spotify: Option<String>,
+    pub deezer: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Branding {
+    /// Visuel de l'émission, utilisé en fond de page et sur l'index.
+    pub cover_url: Option<String>,
+    /// Courte présentation affichée sur l'index des émissions.
+    pub tagline: Option<String>,
+}
+
+impl Show {
+    /// Émission Portes à Potes, utilisée quand la configuration ne déclare aucune émission.
+    pub fn portes_a_potes(feed_url: String, data_file: PathBuf) -> Show {
+        Show {
+            slug: "pap".to_string(),
+            name: "Portes à Potes".to_string(),
+            feed_url,
+            data_file,
+            links: PlatformLinks {
+                apple: Some("https://podcasts.apple.com/fr/podcast/portes-%C3%A0-potes/id1676606425".to_string()),
+                spotify: Some("https://open.spotify.com/show/08mBuJPR173kee3Hj500ol?si=b40b642320db45af".to_string()),
+                deezer: Some("https://dzr.page.link/KSSbPUMwubqNqzgc6".to_string()),
+            },
+            branding: Branding {
+                cover_url: Some(PAP_COVER_URL.to_string()),
+                tagline: None,
+            },
+        }
+    }
+}
+
+/// Un slug valide ne contient que des minuscules ASCII, des chiffres et des tirets.
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty() && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
@@ -0,0 +1,484 @@
+mod json;
+mod sqlite;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::categories;
+use crate::feed::Episode;
+
+pub use json::JsonStore;
+pub use sqlite::{SqliteStore, open_database};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recommendation {
+    /// Identifiant stable ; les anciennes données sans identifiant en reçoivent un au chargement.
+    #[serde(default)]
+    pub id: Uuid,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    /// Identifiants des chroniqueurs dans le registre (`Host::id`).
+    pub chroniqueurs: Vec<String>,
+    pub titre: String,
+    pub lien: Option<String>,
+    /// Identifiant de la catégorie (`Category::id`).
+    pub type_media: String,
+    pub description: String,
+    /// Moment de l'épisode où elle est évoquée, en secondes depuis le début.
+    #[serde(default)]
+    pub timestamp: Option<u32>,
+    /// Les données antérieures à la modération sont considérées comme publiées.
+    #[serde(default)]
+    pub status: Status,
+}
+
+/// État de modération d'une recommandation ; seules les recommandations approuvées sont affichées.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Proposée par un auditeur ou un invité, en attente de modération.
+    Pending,
+    #[default]
+    Approved,
+    Rejected,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Pending => "pending",
+            Status::Approved => "approved",
+            Status::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Status> {
+        match s {
+            "pending" => Some(Status::Pending),
+            "approved" => Some(Status::Approved),
+            "rejected" => Some(Status::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// Contenu saisi d'une recommandation, depuis le modal du site ou l'API JSON.
+#[derive(Debug, Deserialize)]
+pub struct RecommendationInput {
+    pub chroniqueurs: Vec<String>,
+    pub titre: String,
+    #[serde(default)]
+    pub lien: Option<String>,
+    pub type_media: String,
+    pub description: String,
+    #[serde(default)]
+    pub timestamp: Option<u32>,
+}
+
+/// Adresse `http://` ou `https://` : les seules qu'on place dans un `href`, pour qu'un lien
+/// `javascript:` saisi ou venu d'un flux ne s'exécute pas sur le site.
+pub fn is_web_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+impl RecommendationInput {
+    /// Nettoie la saisie (espaces, chroniqueurs vides, lien vide) et refuse une saisie incomplète.
+    /// Le message d'erreur est destiné à être affiché tel quel.
+    pub fn validated(self) -> Result<RecommendationInput, &'static str> {
+        self.cleaned(true)
+    }
+
+    /// Comme [`validated`](Self::validated), pour la suggestion d'un auditeur sans compte : elle
+    /// n'est attribuée à aucun chroniqueur, même si la requête en nomme.
+    pub fn validated_suggestion(self) -> Result<RecommendationInput, &'static str> {
+        RecommendationInput { chroniqueurs: Vec::new(), ..self }.cleaned(false)
+    }
+
+    fn cleaned(self, hosts_required: bool) -> Result<RecommendationInput, &'static str> {
+        let input = RecommendationInput {
+            chroniqueurs: self.chroniqueurs.iter()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            titre: self.titre.trim().to_string(),
+            lien: self.lien.as_deref().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string),
+            type_media: self.type_media.trim().to_string(),
+            description: self.description.trim().to_string(),
+            timestamp: self.timestamp,
+        };
+        if input.titre.is_empty() {
+            return Err("Le titre est obligatoire");
+        }
+        if input.description.is_empty() {
+            return Err("La description est obligatoire");
+        }
+        if hosts_required && input.chroniqueurs.is_empty() {
+            return Err("Indiquez au moins un chroniqueur");
+        }
+        if input.type_media.is_empty() {
+            return Err("Le type est obligatoire");
+        }
+        if let Some(lien) = &input.lien && !is_web_url(lien) {
+            return Err("Le lien doit commencer par http:// ou https://");
+        }
+        Ok(input)
+    }
+
+    /// Nouvelle recommandation avec un identifiant neuf.
+    pub fn into_recommendation(self, status: Status) -> Recommendation {
+        let now = Utc::now();
+        Recommendation {
+            id: Uuid::new_v4(),
+            created_at: now,
+            updated_at: now,
+            chroniqueurs: self.chroniqueurs,
+            titre: self.titre,
+            lien: self.lien,
+            type_media: self.type_media,
+            description: self.description,
+            timestamp: self.timestamp,
+            status,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    NotFound,
+    /// La recommandation a été modifiée depuis la version vue par le client.
+    Conflict,
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Fichier de recommandations existant mais illisible.
+    Corrupt(PathBuf, serde_json::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::NotFound => write!(f, "Recommandation introuvable"),
+            StoreError::Conflict => write!(f, "Cette recommandation a été modifiée entre-temps : rechargez la page"),
+            StoreError::Io(e) => write!(f, "erreur d'accès au fichier : {}", e),
+            StoreError::Json(e) => write!(f, "JSON invalide : {}", e),
+            StoreError::Corrupt(path, e) => write!(
+                f, "{} est illisible ({}) : corrigez-le ou restaurez une sauvegarde", path.display(), e
+            ),
+            StoreError::Sqlite(e) => write!(f, "erreur SQLite : {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<std::io::Error> for StoreError {
+    fn from(e: std::io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Json(e)
+    }
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        StoreError::Sqlite(e)
+    }
+}
+
+/// Recommandations d'une émission, indexées par identifiant d'épisode (`Episode::guid`).
+pub type RecommendationMap = HashMap<String, Vec<Recommendation>>;
+
+/// Stockage des recommandations d'une émission.
+///
+/// Chaque écriture est complète ou n'a pas lieu : en cas d'erreur, le contenu précédent est conservé.
+pub trait Storage: Send + Sync {
+    /// Toutes les recommandations, par épisode, dans l'ordre d'ajout.
+    fn all(&self) -> Result<RecommendationMap, StoreError>;
+    /// Recommandations d'un épisode, dans l'ordre d'ajout.
+    fn list(&self, episode_id: &str) -> Result<Vec<Recommendation>, StoreError>;
+    /// Recommandation `id`, quel que soit son épisode.
+    fn get(&self, id: Uuid) -> Result<Recommendation, StoreError>;
+    /// Épisode auquel est rattachée la recommandation `id`.
+    fn episode_of(&self, id: Uuid) -> Result<String, StoreError>;
+    /// Ajoute une recommandation à la fin de la liste de l'épisode.
+    fn insert(&self, episode_id: &str, rec: &Recommendation) -> Result<(), StoreError>;
+    /// Remplace le contenu de la recommandation `id` sans changer sa place dans la liste.
+    ///
+    /// Si `seen_updated_at` est fourni et ne correspond plus, renvoie `StoreError::Conflict`
+    /// pour ne pas écraser la modification de quelqu'un d'autre.
+    fn update(
+        &self,
+        id: Uuid,
+        seen_updated_at: Option<DateTime<Utc>>,
+        input: RecommendationInput,
+    ) -> Result<Recommendation, StoreError>;
+    /// Change l'état de modération de la recommandation `id`.
+    fn set_status(&self, id: Uuid, status: Status) -> Result<(), StoreError>;
+    /// Retire la recommandation `id`, quel que soit son épisode.
+    fn delete(&self, id: Uuid) -> Result<(), StoreError>;
+    /// Remplace tout le contenu (import, migrations).
+    fn replace_all(&self, map: &RecommendationMap) -> Result<(), StoreError>;
+    /// Compteur incrémenté à chaque écriture réussie (index de recherche à reconstruire).
+    fn revision(&self) -> u64;
+}
+
+pub type RecommendationsStore = Arc<dyn Storage>;
+
+/// Applique une modification à une recommandation après avoir vérifié qu'elle n'a pas changé entre-temps.
+fn apply_update(
+    rec: &mut Recommendation,
+    seen_updated_at: Option<DateTime<Utc>>,
+    input: RecommendationInput,
+) -> Result<(), StoreError> {
+    if seen_updated_at.is_some_and(|seen| seen != rec.updated_at) {
+        return Err(StoreError::Conflict);
+    }
+    rec.chroniqueurs = input.chroniqueurs;
+    rec.titre = input.titre;
+    rec.lien = input.lien;
+    rec.type_media = input.type_media;
+    rec.description = input.description;
+    rec.timestamp = input.timestamp;
+    rec.updated_at = Utc::now();
+    Ok(())
+}
+
+/// Lit un fichier JSON de recommandations (stockage JSON ou fichier d'import).
+///
+/// Les recommandations enregistrées avant l'introduction des identifiants en reçoivent un, et
+/// les types en texte libre (« chaine youtube ») sont ramenés aux catégories de départ ; le
+/// booléen indique si le contenu a été ainsi mis à jour.
+pub fn read_json_file(path: &Path) -> Result<(RecommendationMap, bool), StoreError> {
+    let json = fs::read_to_string(path)?;
+    let corrupt = |e| StoreError::Corrupt(path.to_path_buf(), e);
+    let mut value: serde_json::Value = serde_json::from_str(&json).map_err(corrupt)?;
+    let mut upgraded = false;
+    if let Some(episodes) = value.as_object_mut() {
+        let types = episodes.values_mut()
+            .filter_map(serde_json::Value::as_array_mut)
+            .flatten()
+            .filter_map(|rec| rec.get_mut("type_media"));
+        for type_media in types {
+            if let Some(id) = type_media.as_str().and_then(categories::legacy_id) {
+                *type_media = id.into();
+                upgraded = true;
+            }
+        }
+    }
+    let mut map: RecommendationMap = serde_json::from_value(value).map_err(corrupt)?;
+    for rec in map.values_mut().flatten().filter(|rec| rec.id.is_nil()) {
+        rec.id = Uuid::new_v4();
+        upgraded = true;
+    }
+    Ok((map, upgraded))
+}
+
+/// Écrit des données au format JSON (stockage JSON, export, registre des chroniqueurs).
+///
+/// Le contenu est écrit dans un fichier temporaire, synchronisé sur disque, puis renommé
+/// par-dessus l'original : après un arrêt brutal, le fichier contient l'ancienne ou la
+/// nouvelle version, jamais une version tronquée.
+pub fn write_json_file<T: Serialize>(path: &Path, value: &T) -> Result<(), StoreError> {
+    let json = serde_json::to_string_pretty(value)?;
+    let tmp = with_suffix(path, ".tmp");
+    let written = File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(json.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    sync_dir(parent_dir(path))?;
+    Ok(())
+}
+
+/// `path` suivi de `suffix` (`recommandations.json` → `recommandations.json.tmp`).
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Répertoire contenant `path` (`.` pour un chemin relatif sans répertoire).
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Synchronise le répertoire pour que le renommage survive à une coupure de courant.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Bilan de la migration des clés « titre d'épisode » vers les guid.
+#[derive(Debug, Default)]
+pub struct GuidMigration {
+    /// Clés renommées : (ancien titre, guid).
+    pub migrated: Vec<(String, String)>,
+    /// Clés qui ne correspondent ni à un guid ni à un titre du flux ; elles sont conservées telles quelles.
+    pub unmatched: Vec<String>,
+}
+
+/// Réindexe par guid les recommandations encore rangées sous le titre de leur épisode.
+///
+/// Les anciennes données utilisaient le titre comme clé : on les rattache à l'épisode du flux
+/// portant ce titre. Les clés déjà égales à un guid ne sont pas touchées, ce qui rend la
+/// migration sans effet une fois faite.
+pub fn migrate_title_keys(map: &mut RecommendationMap, episodes: &[Episode]) -> GuidMigration {
+    let mut report = GuidMigration::default();
+    let legacy_keys: Vec<String> = map.keys()
+        .filter(|key| !episodes.iter().any(|ep| &ep.guid == *key))
+        .cloned()
+        .collect();
+    for key in legacy_keys {
+        match episodes.iter().find(|ep| ep.title.trim() == key.trim()) {
+            Some(ep) => {
+                let recos = map.remove(&key).unwrap_or_default();
+                map.entry(ep.guid.clone()).or_default().extend(recos);
+                report.migrated.push((key, ep.guid.clone()));
+            }
+            None => report.unmatched.push(key),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(guid: &str, title: &str) -> Episode {
+        Episode { guid: guid.to_string(), title: title.to_string(), ..Default::default() }
+    }
+
+    fn rec(titre: &str) -> Recommendation {
+        RecommendationInput {
+            chroniqueurs: vec!["jean".to_string()],
+            titre: titre.to_string(),
+            lien: None,
+            type_media: "livre".to_string(),
+            description: "Une description".to_string(),
+            timestamp: None,
+        }.into_recommendation(Status::Approved)
+    }
+
+    fn titles(map: &RecommendationMap, key: &str) -> Vec<String> {
+        map.get(key).map(|list| list.iter().map(|r| r.titre.clone()).collect()).unwrap_or_default()
+    }
+
+    #[test]
+    fn title_keys_move_to_the_guid_of_the_episode() {
+        let episodes = [episode("guid-1", "PAP S1/E1 - Début"), episode("guid-2", "PAP S1/E2 - Suite")];
+        let mut map = RecommendationMap::new();
+        map.insert("PAP S1/E1 - Début".to_string(), vec![rec("Ancien")]);
+        map.insert(" PAP S1/E2 - Suite ".to_string(), vec![rec("Espaces")]);
+        map.insert("guid-1".to_string(), vec![rec("Récent")]);
+        map.insert("Épisode retiré du flux".to_string(), vec![rec("Orphelin")]);
+
+        let report = migrate_title_keys(&mut map, &episodes);
+
+        assert_eq!(report.migrated.len(), 2);
+        assert_eq!(report.unmatched, vec!["Épisode retiré du flux".to_string()]);
+        assert_eq!(titles(&map, "guid-1"), vec!["Récent", "Ancien"]);
+        assert_eq!(titles(&map, "guid-2"), vec!["Espaces"]);
+        assert_eq!(titles(&map, "Épisode retiré du flux"), vec!["Orphelin"]);
+        assert!(!map.contains_key("PAP S1/E1 - Début"));
+    }
+
+    #[test]
+    fn title_key_migration_is_idempotent() {
+        let episodes = [episode("guid-1", "Début")];
+        let mut map = RecommendationMap::new();
+        map.insert("Début".to_string(), vec![rec("Ancien")]);
+        map.insert("Inconnu".to_string(), vec![rec("Orphelin")]);
+
+        migrate_title_keys(&mut map, &episodes);
+        let once: Vec<(String, Vec<Uuid>)> = sorted_ids(&map);
+        let report = migrate_title_keys(&mut map, &episodes);
+
+        assert!(report.migrated.is_empty());
+        assert_eq!(report.unmatched, vec!["Inconnu".to_string()]);
+        assert_eq!(sorted_ids(&map), once);
+    }
+
+    #[test]
+    fn a_guid_equal_to_another_episode_title_is_left_alone() {
+        // Flux sans <guid> : l'identifiant est le titre, déjà une clé valide
+        let episodes = [episode("Début", "Début"), episode("guid-2", "Suite")];
+        let mut map = RecommendationMap::new();
+        map.insert("Début".to_string(), vec![rec("Ancien")]);
+
+        let report = migrate_title_keys(&mut map, &episodes);
+
+        assert!(report.migrated.is_empty() && report.unmatched.is_empty());
+        assert_eq!(titles(&map, "Début"), vec!["Ancien"]);
+    }
+
+    fn sorted_ids(map: &RecommendationMap) -> Vec<(String, Vec<Uuid>)> {
+        let mut entries: Vec<(String, Vec<Uuid>)> = map.iter()
+            .map(|(key, list)| (key.clone(), list.iter().map(|r| r.id).collect()))
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn listener_suggestions_have_no_host() {
+        let input = || RecommendationInput {
+            chroniqueurs: vec!["jean".to_string()],
+            titre: " Titre ".to_string(),
+            lien: Some(" ".to_string()),
+            type_media: "livre".to_string(),
+            description: "Une description".to_string(),
+            timestamp: None,
+        };
+        let suggestion = input().validated_suggestion().unwrap();
+        assert!(suggestion.chroniqueurs.is_empty());
+        assert_eq!((suggestion.titre.as_str(), suggestion.lien), ("Titre", None));
+
+        assert!(RecommendationInput { chroniqueurs: Vec::new(), ..input() }.validated().is_err());
+        assert!(RecommendationInput { description: " ".to_string(), ..input() }.validated_suggestion().is_err());
+    }
+
+    #[test]
+    fn episode_of_finds_the_episode_in_both_backends() {
+        let path = std::env::temp_dir().join(format!("pap-episode-of-{}.json", Uuid::new_v4()));
+        let backends: [RecommendationsStore; 2] = [
+            Arc::new(JsonStore::open(&path, 0).unwrap()),
+            Arc::new(SqliteStore::new(open_database(Path::new(":memory:")).unwrap(), "pap")),
+        ];
+        for store in backends {
+            let (first, second) = (rec("Premier"), rec("Second"));
+            store.insert("guid-1", &first).unwrap();
+            store.insert("guid-2", &second).unwrap();
+
+            assert_eq!(store.episode_of(first.id).unwrap(), "guid-1");
+            assert_eq!(store.episode_of(second.id).unwrap(), "guid-2");
+            assert!(matches!(store.episode_of(Uuid::new_v4()), Err(StoreError::NotFound)));
+        }
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,171 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{
+    Recommendation, RecommendationInput, RecommendationMap, Status, Storage, StoreError,
+    apply_update, parent_dir, read_json_file, with_suffix, write_json_file,
+};
+
+/// Stockage dans un fichier JSON, réécrit entièrement (et de façon atomique) à chaque modification.
+pub struct JsonStore {
+    path: PathBuf,
+    /// Nombre de sauvegardes horodatées conservées (0 : aucune).
+    backups: usize,
+    map: Mutex<RecommendationMap>,
+    revision: AtomicU64,
+}
+
+impl JsonStore {
+    /// Ouvre le fichier, ou part d'un contenu vide s'il n'existe pas encore.
+    ///
+    /// Un fichier présent mais illisible est une erreur : démarrer à vide reviendrait à
+    /// l'écraser à la prochaine modification.
+    pub fn open(path: &Path, backups: usize) -> Result<JsonStore, StoreError> {
+        let store = JsonStore { path: path.to_path_buf(), backups, map: Mutex::new(RecommendationMap::new()),
+            revision: AtomicU64::new(0),
+        };
+        match read_json_file(path) {
+            Ok((map, upgraded)) => {
+                if upgraded {
+                    store.save(&map)?;
+                }
+                *store.map.lock().unwrap() = map;
+            }
+            Err(StoreError::Io(e)) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(store)
+    }
+
+    /// Sauvegarde la version actuelle du fichier puis la remplace par `map`.
+    fn save(&self, map: &RecommendationMap) -> Result<(), StoreError> {
+        if self.backups > 0 {
+            // Une sauvegarde ratée ne doit pas empêcher l'enregistrement
+            if let Err(e) = self.backup() {
+                eprintln!("Sauvegarde de {} impossible : {}", self.path.display(), e);
+            }
+        }
+        write_json_file(&self.path, map)
+    }
+
+    /// Copie le fichier sous un nom horodaté (`recommandations.json.20240131-120000.000.bak`)
+    /// et supprime les sauvegardes les plus anciennes au-delà de `backups`.
+    fn backup(&self) -> std::io::Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let stamp = Utc::now().format("%Y%m%d-%H%M%S%.3f");
+        let target = with_suffix(&self.path, &format!(".{}.bak", stamp));
+        // Le fichier est remplacé par renommage : un lien physique suffit à garder l'ancienne version
+        if fs::hard_link(&self.path, &target).is_err() {
+            fs::copy(&self.path, &target)?;
+        }
+
+        let prefix = format!("{}.", self.path.file_name().unwrap_or_default().to_string_lossy());
+        let mut existing: Vec<PathBuf> = fs::read_dir(parent_dir(&self.path))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|p| p.file_name()
+                .map(|name| name.to_string_lossy())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".bak")))
+            .collect();
+        // L'horodatage rend l'ordre alphabétique chronologique
+        existing.sort();
+        let excess = existing.len().saturating_sub(self.backups);
+        for old in &existing[..excess] {
+            fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+
+    /// Applique `change` à une copie, l'écrit sur disque, puis seulement la garde en mémoire :
+    /// une écriture ratée laisse le contenu précédent intact.
+    fn modify<T>(&self, change: impl FnOnce(&mut RecommendationMap) -> Result<T, StoreError>) -> Result<T, StoreError> {
+        let mut map = self.map.lock().unwrap();
+        let mut updated = map.clone();
+        let result = change(&mut updated)?;
+        self.save(&updated)?;
+        *map = updated;
+        self.revision.fetch_add(1, Ordering::Relaxed);
+        Ok(result)
+    }
+}
+
+impl Storage for JsonStore {
+    fn all(&self) -> Result<RecommendationMap, StoreError> {
+        Ok(self.map.lock().unwrap().clone())
+    }
+
+    fn list(&self, episode_id: &str) -> Result<Vec<Recommendation>, StoreError> {
+        Ok(self.map.lock().unwrap().get(episode_id).cloned().unwrap_or_default())
+    }
+
+    fn get(&self, id: Uuid) -> Result<Recommendation, StoreError> {
+        let map = self.map.lock().unwrap();
+        map.values().flatten().find(|rec| rec.id == id).cloned().ok_or(StoreError::NotFound)
+    }
+
+    fn episode_of(&self, id: Uuid) -> Result<String, StoreError> {
+        let map = self.map.lock().unwrap();
+        map.iter()
+            .find(|(_, list)| list.iter().any(|rec| rec.id == id))
+            .map(|(episode_id, _)| episode_id.clone())
+            .ok_or(StoreError::NotFound)
+    }
+
+    fn insert(&self, episode_id: &str, rec: &Recommendation) -> Result<(), StoreError> {
+        self.modify(|map| {
+            map.entry(episode_id.to_string()).or_default().push(rec.clone());
+            Ok(())
+        })
+    }
+
+    fn update(
+        &self,
+        id: Uuid,
+        seen_updated_at: Option<DateTime<Utc>>,
+        input: RecommendationInput,
+    ) -> Result<Recommendation, StoreError> {
+        self.modify(|map| {
+            let rec = map.values_mut().flatten().find(|rec| rec.id == id).ok_or(StoreError::NotFound)?;
+            apply_update(rec, seen_updated_at, input)?;
+            Ok(rec.clone())
+        })
+    }
+
+    fn set_status(&self, id: Uuid, status: Status) -> Result<(), StoreError> {
+        self.modify(|map| {
+            let rec = map.values_mut().flatten().find(|rec| rec.id == id).ok_or(StoreError::NotFound)?;
+            rec.status = status;
+            rec.updated_at = Utc::now();
+            Ok(())
+        })
+    }
+
+    fn delete(&self, id: Uuid) -> Result<(), StoreError> {
+        self.modify(|map| {
+            for list in map.values_mut() {
+                if let Some(idx) = list.iter().position(|rec| rec.id == id) {
+                    list.remove(idx);
+                    return Ok(());
+                }
+            }
+            Err(StoreError::NotFound)
+        })
+    }
+
+    fn replace_all(&self, new_map: &RecommendationMap) -> Result<(), StoreError> {
+        self.modify(|map| {
+            *map = new_map.clone();
+            Ok(())
+        })
+    }
+
+    fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+}
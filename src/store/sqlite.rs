@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use chrono::{DateTime, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{Connection, OptionalExtension, Row, ToSql, Transaction, params};
+use uuid::Uuid;
+use crate::categories;
+use super::{
+    Recommendation, RecommendationInput, RecommendationMap, Status, Storage, StoreError, apply_update,
+};
+
+/// Étape de [`MIGRATIONS`].
+enum Migration {
+    Sql(&'static str),
+    /// Migration qui a besoin du code de l'application.
+    Code(fn(&Transaction) -> rusqlite::Result<()>),
+}
+
+/// Migrations du schéma, appliquées dans l'ordre ; `PRAGMA user_version` retient la dernière appliquée.
+/// Ne jamais modifier une migration publiée : en ajouter une nouvelle à la fin.
+const MIGRATIONS: &[Migration] = &[
+    // 1 : recommandations et leurs chroniqueurs
+    Migration::Sql("CREATE TABLE recommendations (
+        id TEXT PRIMARY KEY NOT NULL,
+        show TEXT NOT NULL,
+        episode_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        titre TEXT NOT NULL,
+        lien TEXT,
+        type_media TEXT NOT NULL,
+        description TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE INDEX recommendations_by_episode ON recommendations (show, episode_id, position);
+    CREATE TABLE recommendation_chroniqueurs (
+        recommendation_id TEXT NOT NULL REFERENCES recommendations (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        PRIMARY KEY (recommendation_id, position)
+    );"),
+    // 2 : état de modération ; les recommandations existantes sont publiées
+    Migration::Sql("ALTER TABLE recommendations ADD COLUMN status TEXT NOT NULL DEFAULT 'approved';"),
+    // 3 : les chroniqueurs sont désignés par leur identifiant dans le registre
+    Migration::Sql("ALTER TABLE recommendation_chroniqueurs RENAME COLUMN name TO host_id;"),
+    // 4 : types en texte libre ramenés aux catégories, par la même règle que le stockage JSON
+    Migration::Code(migrate_legacy_types),
+    // 5 : moment de l'épisode où la recommandation est évoquée, en secondes
+    Migration::Sql("ALTER TABLE recommendations ADD COLUMN timestamp INTEGER;"),
+];
+
+fn migrate_legacy_types(tx: &Transaction) -> rusqlite::Result<()> {
+    let types: Vec<String> = tx.prepare("SELECT DISTINCT type_media FROM recommendations")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for text in types {
+        if let Some(id) = categories::legacy_id(&text) {
+            tx.execute("UPDATE recommendations SET type_media = ?1 WHERE type_media = ?2", params![id, text])?;
+        }
+    }
+    Ok(())
+}
+
+/// Ouvre (ou crée) la base partagée par les émissions et applique les migrations en attente.
+pub fn open_database(path: &Path) -> Result<Arc<Mutex<Connection>>, StoreError> {
+    let mut conn = Connection::open(path)?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    migrate(&mut conn, MIGRATIONS.len())?;
+    Ok(Arc::new(Mutex::new(conn)))
+}
+
+/// Applique les migrations en attente jusqu'à la version `target`.
+fn migrate(conn: &mut Connection, target: usize) -> Result<(), StoreError> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().take(target).skip(version as usize) {
+        let tx = conn.transaction()?;
+        match migration {
+            Migration::Sql(sql) => tx.execute_batch(sql)?,
+            Migration::Code(run) => run(&tx)?,
+        }
+        tx.pragma_update(None, "user_version", i as i64 + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Recommandations d'une émission dans la base SQLite ; `show` sert d'espace de noms.
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+    show: String,
+    revision: AtomicU64,
+}
+
+impl SqliteStore {
+    pub fn new(conn: Arc<Mutex<Connection>>, show: &str) -> SqliteStore {
+        SqliteStore { conn, show: show.to_string(), revision: AtomicU64::new(0) }
+    }
+
+    /// À appeler une fois l'écriture validée.
+    fn changed(&self) {
+        self.revision.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+const SELECT_RECOMMENDATION: &str =
+    "SELECT id, episode_id, titre, lien, type_media, description, created_at, updated_at, status, timestamp FROM recommendations";
+
+impl ToSql for Status {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Status {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Status::parse(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+fn parse_id(text: String) -> rusqlite::Result<Uuid> {
+    Uuid::parse_str(&text).map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+/// Ligne de `SELECT_RECOMMENDATION` : (épisode, recommandation sans ses chroniqueurs).
+fn read_row(row: &Row) -> rusqlite::Result<(String, Recommendation)> {
+    Ok((row.get(1)?, Recommendation {
+        id: parse_id(row.get(0)?)?,
+        titre: row.get(2)?,
+        lien: row.get(3)?,
+        type_media: row.get(4)?,
+        description: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+        status: row.get(8)?,
+        timestamp: row.get(9)?,
+        chroniqueurs: Vec::new(),
+    }))
+}
+
+/// Chroniqueurs des recommandations de l'émission, dans leur ordre de saisie.
+fn chroniqueurs_by_recommendation(conn: &Connection, show: &str) -> Result<HashMap<Uuid, Vec<String>>, StoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT c.recommendation_id, c.host_id FROM recommendation_chroniqueurs c
+         JOIN recommendations r ON r.id = c.recommendation_id
+         WHERE r.show = ?1 ORDER BY c.recommendation_id, c.position",
+    )?;
+    let mut by_id: HashMap<Uuid, Vec<String>> = HashMap::new();
+    let rows = stmt.query_map([show], |row| Ok((parse_id(row.get(0)?)?, row.get::<_, String>(1)?)))?;
+    for row in rows {
+        let (id, host_id) = row?;
+        by_id.entry(id).or_default().push(host_id);
+    }
+    Ok(by_id)
+}
+
+fn insert_row(tx: &Transaction, show: &str, episode_id: &str, position: i64, rec: &Recommendation) -> Result<(), StoreError> {
+    tx.execute(
+        "INSERT INTO recommendations (id, show, episode_id, position, titre, lien, type_media, description, created_at, updated_at, status, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            rec.id.to_string(), show, episode_id, position, rec.titre, rec.lien,
+            rec.type_media, rec.description, rec.created_at, rec.updated_at, rec.status, rec.timestamp,
+        ],
+    )?;
+    write_chroniqueurs(tx, rec)
+}
+
+fn write_chroniqueurs(tx: &Transaction, rec: &Recommendation) -> Result<(), StoreError> {
+    tx.execute("DELETE FROM recommendation_chroniqueurs WHERE recommendation_id = ?1", [rec.id.to_string()])?;
+    for (position, host_id) in rec.chroniqueurs.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO recommendation_chroniqueurs (recommendation_id, position, host_id) VALUES (?1, ?2, ?3)",
+            params![rec.id.to_string(), position as i64, host_id],
+        )?;
+    }
+    Ok(())
+}
+
+impl Storage for SqliteStore {
+    fn all(&self) -> Result<RecommendationMap, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut chroniqueurs = chroniqueurs_by_recommendation(&conn, &self.show)?;
+        let mut stmt = conn.prepare(&format!("{} WHERE show = ?1 ORDER BY episode_id, position", SELECT_RECOMMENDATION))?;
+        let mut map = RecommendationMap::new();
+        for row in stmt.query_map([&self.show], read_row)? {
+            let (episode_id, mut rec) = row?;
+            rec.chroniqueurs = chroniqueurs.remove(&rec.id).unwrap_or_default();
+            map.entry(episode_id).or_default().push(rec);
+        }
+        Ok(map)
+    }
+
+    fn list(&self, episode_id: &str) -> Result<Vec<Recommendation>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut chroniqueurs = chroniqueurs_by_recommendation(&conn, &self.show)?;
+        let mut stmt = conn.prepare(&format!("{} WHERE show = ?1 AND episode_id = ?2 ORDER BY position", SELECT_RECOMMENDATION))?;
+        let mut list = Vec::new();
+        for row in stmt.query_map([&self.show, episode_id], read_row)? {
+            let (_, mut rec) = row?;
+            rec.chroniqueurs = chroniqueurs.remove(&rec.id).unwrap_or_default();
+            list.push(rec);
+        }
+        Ok(list)
+    }
+
+    fn get(&self, id: Uuid) -> Result<Recommendation, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let (_, mut rec) = conn
+            .query_row(
+                &format!("{} WHERE show = ?1 AND id = ?2", SELECT_RECOMMENDATION),
+                [&self.show, &id.to_string()],
+                read_row,
+            )
+            .optional()?
+            .ok_or(StoreError::NotFound)?;
+        let mut stmt = conn.prepare(
+            "SELECT host_id FROM recommendation_chroniqueurs WHERE recommendation_id = ?1 ORDER BY position",
+        )?;
+        rec.chroniqueurs = stmt.query_map([id.to_string()], |row| row.get(0))?.collect::<Result<_, _>>()?;
+        Ok(rec)
+    }
+
+    fn episode_of(&self, id: Uuid) -> Result<String, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT episode_id FROM recommendations WHERE show = ?1 AND id = ?2",
+            [&self.show, &id.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or(StoreError::NotFound)
+    }
+
+    fn insert(&self, episode_id: &str, rec: &Recommendation) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let position: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM recommendations WHERE show = ?1 AND episode_id = ?2",
+            [&self.show, episode_id],
+            |row| row.get(0),
+        )?;
+        insert_row(&tx, &self.show, episode_id, position, rec)?;
+        tx.commit()?;
+        self.changed();
+        Ok(())
+    }
+
+    fn update(
+        &self,
+        id: Uuid,
+        seen_updated_at: Option<DateTime<Utc>>,
+        input: RecommendationInput,
+    ) -> Result<Recommendation, StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let (_, mut rec) = tx
+            .query_row(
+                &format!("{} WHERE show = ?1 AND id = ?2", SELECT_RECOMMENDATION),
+                [&self.show, &id.to_string()],
+                read_row,
+            )
+            .optional()?
+            .ok_or(StoreError::NotFound)?;
+        apply_update(&mut rec, seen_updated_at, input)?;
+        tx.execute(
+            "UPDATE recommendations SET titre = ?1, lien = ?2, type_media = ?3, description = ?4, timestamp = ?5, updated_at = ?6 WHERE id = ?7",
+            params![rec.titre, rec.lien, rec.type_media, rec.description, rec.timestamp, rec.updated_at, rec.id.to_string()],
+        )?;
+        write_chroniqueurs(&tx, &rec)?;
+        tx.commit()?;
+        self.changed();
+        Ok(rec)
+    }
+
+    fn set_status(&self, id: Uuid, status: Status) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE recommendations SET status = ?1, updated_at = ?2 WHERE show = ?3 AND id = ?4",
+            params![status, Utc::now(), self.show, id.to_string()],
+        )?;
+        if updated == 0 {
+            return Err(StoreError::NotFound);
+        }
+        self.changed();
+        Ok(())
+    }
+
+    fn delete(&self, id: Uuid) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute(
+            "DELETE FROM recommendations WHERE show = ?1 AND id = ?2",
+            [&self.show, &id.to_string()],
+        )?;
+        if deleted == 0 {
+            return Err(StoreError::NotFound);
+        }
+        self.changed();
+        Ok(())
+    }
+
+    fn replace_all(&self, map: &RecommendationMap) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM recommendations WHERE show = ?1", [&self.show])?;
+        for (episode_id, list) in map {
+            for (position, rec) in list.iter().enumerate() {
+                insert_row(&tx, &self.show, episode_id, position as i64, rec)?;
+            }
+        }
+        tx.commit()?;
+        self.changed();
+        Ok(())
+    }
+
+    fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::read_json_file;
+
+    const LEGACY_TYPES: [&str; 6] = ["Chaîne YouTube", "Série", "livre", "podcast", "Podcast !", "musique classique"];
+
+    /// Types enregistrés avant la migration 4, puis migrés.
+    fn migrated_types(runs: usize) -> Vec<String> {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, 3).unwrap();
+        for (i, text) in LEGACY_TYPES.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO recommendations (id, show, episode_id, position, titre, type_media, description, created_at, updated_at)
+                VALUES (?1, 'pap', 'guid', ?2, 'Titre', ?3, 'Description', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z')",
+                params![Uuid::new_v4().to_string(), i as i64, text],
+            ).unwrap();
+        }
+        for _ in 0..runs {
+            migrate(&mut conn, MIGRATIONS.len()).unwrap();
+        }
+        conn.prepare("SELECT type_media FROM recommendations ORDER BY position").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn legacy_types_migrate_like_the_json_store() {
+        let json = serde_json::json!({
+            "guid": LEGACY_TYPES.iter().map(|text| serde_json::json!({
+                "chroniqueurs": ["jean"], "titre": "Titre", "type_media": text, "description": "Description"
+            })).collect::<Vec<_>>()
+        });
+        let path = std::env::temp_dir().join(format!("pap-legacy-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, json.to_string()).unwrap();
+        let (map, _) = read_json_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let from_json: Vec<String> = map["guid"].iter().map(|rec| rec.type_media.clone()).collect();
+
+        assert_eq!(migrated_types(1), from_json);
+        assert_eq!(from_json, ["chaine", "serie", "livre", "podcast", "autre", "musique"]);
+    }
+
+    #[test]
+    fn migrations_apply_once() {
+        assert_eq!(migrated_types(2), migrated_types(1));
+    }
+}
@@ -0,0 +1,112 @@
+//! Limite par adresse de visiteur sur une période glissante : propositions sans compte,
+//! échecs de connexion.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use axum::http::{HeaderMap, HeaderName};
+
+/// Actions récentes par adresse, au plus `limit` pendant `window`.
+pub struct Throttle {
+    client_ip_header: Option<HeaderName>,
+    limit: usize,
+    window: Duration,
+    recent: Mutex<HashMap<IpAddr, Vec<Instant>>>,
+}
+
+impl Throttle {
+    /// `client_ip_header` : voir `Config::client_ip_header`.
+    pub fn new(client_ip_header: Option<HeaderName>, limit: usize, window_secs: u64) -> Throttle {
+        Throttle { client_ip_header, limit, window: Duration::from_secs(window_secs), recent: Mutex::default() }
+    }
+
+    /// Adresse du visiteur : la dernière de l'en-tête du proxy (celle qu'il a lui-même ajoutée),
+    /// à défaut celle de la connexion.
+    fn client_ip(&self, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+        self.client_ip_header.as_ref()
+            .and_then(|name| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer.ip())
+    }
+
+    /// Actions de l'adresse encore dans la fenêtre (les plus anciennes oubliées au passage).
+    fn with_recent<T>(&self, headers: &HeaderMap, peer: SocketAddr, f: impl FnOnce(&mut Vec<Instant>, Instant) -> T) -> T {
+        let ip = self.client_ip(headers, peer);
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap();
+        recent.retain(|_, times| {
+            times.retain(|t| now.duration_since(*t) < self.window);
+            !times.is_empty()
+        });
+        f(recent.entry(ip).or_default(), now)
+    }
+
+    /// Compte une action ; `Err(secondes)` si l'adresse a atteint sa limite.
+    pub fn check(&self, headers: &HeaderMap, peer: SocketAddr) -> Result<(), u64> {
+        self.with_recent(headers, peer, |times, now| {
+            if times.len() >= self.limit {
+                return Err((self.window - now.duration_since(times[0])).as_secs().max(1));
+            }
+            times.push(now);
+            Ok(())
+        })
+    }
+
+    /// Rend la dernière action comptée par `check` (connexion finalement réussie) : compter
+    /// avant de vérifier évite que des tentatives simultanées dépassent la limite.
+    pub fn release(&self, headers: &HeaderMap, peer: SocketAddr) {
+        self.with_recent(headers, peer, |times, _| times.pop());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(ip: &str) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), 40000)
+    }
+
+    #[test]
+    fn check_counts_until_the_limit() {
+        let throttle = Throttle::new(None, 2, 600);
+        let headers = HeaderMap::new();
+        assert!(throttle.check(&headers, peer("10.0.0.1")).is_ok());
+        assert!(throttle.check(&headers, peer("10.0.0.1")).is_ok());
+        let wait = throttle.check(&headers, peer("10.0.0.1")).unwrap_err();
+        assert!((1..=600).contains(&wait));
+        assert!(throttle.check(&headers, peer("10.0.0.2")).is_ok());
+    }
+
+    #[test]
+    fn released_actions_do_not_count() {
+        let throttle = Throttle::new(None, 2, 600);
+        let headers = HeaderMap::new();
+        for _ in 0..5 {
+            assert!(throttle.check(&headers, peer("10.0.0.1")).is_ok());
+            throttle.release(&headers, peer("10.0.0.1"));
+        }
+        assert!(throttle.check(&headers, peer("10.0.0.1")).is_ok());
+        assert!(throttle.check(&headers, peer("10.0.0.1")).is_ok());
+        assert!(throttle.check(&headers, peer("10.0.0.1")).is_err());
+        // Rien à rendre pour une adresse inconnue
+        throttle.release(&headers, peer("10.0.0.9"));
+    }
+
+    #[test]
+    fn proxy_header_gives_the_visitor_address() {
+        let throttle = Throttle::new(Some(HeaderName::from_static("x-forwarded-for")), 1, 600);
+        let mut headers = HeaderMap::new();
+        // La dernière adresse est celle ajoutée par le proxy ; la première peut être inventée
+        headers.insert("x-forwarded-for", "1.2.3.4, 203.0.113.7".parse().unwrap());
+        assert!(throttle.check(&headers, peer("127.0.0.1")).is_ok());
+        headers.insert("x-forwarded-for", "5.6.7.8, 203.0.113.7".parse().unwrap());
+        assert!(throttle.check(&headers, peer("127.0.0.1")).is_err());
+        headers.insert("x-forwarded-for", "pas une adresse".parse().unwrap());
+        assert!(throttle.check(&headers, peer("127.0.0.1")).is_ok());
+        assert!(throttle.check(&HeaderMap::new(), peer("127.0.0.1")).is_err());
+    }
+}